@@ -0,0 +1,297 @@
+use std::borrow::Cow;
+
+use bevy::tasks::TaskPool;
+
+use crate::{
+    parallel::{compute_pool, for_each_row},
+    rule::Rule,
+    topology::Topology,
+    universe::{StepChanges, Universe},
+};
+
+/// Bounded board packing 64 cells into each `u64`, stepped by a
+/// bit-parallel adder so a whole word of cells is updated at once.
+///
+/// Bit `i` of word `k` in a row is the cell at `x = 64 * k + i`; bits past
+/// the right edge of the last word are always zero.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+/// A row of cells together with the cells just past its left and right
+/// ends, as seen through the topology.
+struct Row<'a> {
+    words: Cow<'a, [u64]>,
+    left: bool,
+    right: bool,
+}
+
+/// Neighbour counts of a word of cells as four bit-planes (`1, 2, 4, 8`).
+struct Counts([u64; 4]);
+
+impl Counts {
+    fn of(neighbours: [u64; 8]) -> Counts {
+        let [aw, a, ae, w, e, bw, b, be] = neighbours;
+
+        let (s0, c0) = full_add(aw, a, ae);
+        let (s1, c1) = full_add(w, e, bw);
+        let (s2, c2) = (b ^ be, b & be);
+        let (ones, c3) = full_add(s0, s1, s2);
+        let (t, d0) = full_add(c0, c1, c2);
+        let (twos, d1) = (t ^ c3, t & c3);
+
+        Counts([ones, twos, d0 ^ d1, d0 & d1])
+    }
+
+    /// Mask of the cells whose neighbour count is exactly `n`.
+    fn equal_to(&self, n: u8) -> u64 {
+        self.0.iter().enumerate().fold(!0, |mask, (bit, &plane)| {
+            mask & if n & 1 << bit != 0 { plane } else { !plane }
+        })
+    }
+}
+
+fn full_add(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let partial = a ^ b;
+    (partial ^ c, (a & b) | (c & partial))
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize, topology: Topology) -> Self {
+        let words_per_row = width.div_ceil(64);
+
+        BitGrid {
+            width,
+            height,
+            topology,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn on_board(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        let x = usize::try_from(x).ok().filter(|&x| x < self.width)?;
+        let y = usize::try_from(y).ok().filter(|&y| y < self.height)?;
+        Some((x, y))
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    fn cell(&self, x: usize, y: usize) -> bool {
+        self.row(y)[x / 64] & 1 << (x % 64) != 0
+    }
+
+    fn resolved(&self, x: isize, y: isize) -> bool {
+        self.topology
+            .resolve(x, y, self.width, self.height)
+            .is_some_and(|(x, y)| self.cell(x, y))
+    }
+
+    /// Row `y`, which may be one past the top or bottom edge.
+    fn extended_row(&self, y: isize) -> Row<'_> {
+        let left = self.resolved(-1, y);
+        let right = self.resolved(self.width as isize, y);
+
+        let words = if (0..self.height as isize).contains(&y) {
+            Cow::Borrowed(self.row(y as usize))
+        } else {
+            let mut words = vec![0; self.words_per_row];
+            for x in 0..self.width {
+                if self.resolved(x as isize, y) {
+                    words[x / 64] |= 1 << (x % 64);
+                }
+            }
+            Cow::Owned(words)
+        };
+
+        Row { words, left, right }
+    }
+
+    /// The row shifted so that each bit holds its west and east neighbour.
+    fn shifted(&self, row: &Row<'_>, k: usize) -> (u64, u64) {
+        let words = &row.words;
+        let last = self.words_per_row - 1;
+
+        let carry_west = if k == 0 {
+            u64::from(row.left)
+        } else {
+            words[k - 1] >> 63
+        };
+        let carry_east = if k == last {
+            u64::from(row.right) << ((self.width - 1) % 64)
+        } else {
+            words[k + 1] << 63
+        };
+
+        (words[k] << 1 | carry_west, words[k] >> 1 | carry_east)
+    }
+
+    pub fn step(&mut self, rule: &Rule) {
+        self.step_in(rule, compute_pool());
+    }
+
+    pub(crate) fn step_in(&mut self, rule: &Rule, pool: &TaskPool) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        let born: Vec<u8> = (0..=8).filter(|&n| rule.next_state(false, n)).collect();
+        let survive: Vec<u8> = (0..=8).filter(|&n| rule.next_state(true, n)).collect();
+
+        let tail_mask = match self.width % 64 {
+            0 => !0,
+            bits => (1 << bits) - 1,
+        };
+
+        let mut next = vec![0; self.words.len()];
+
+        for_each_row(pool, &mut next, self.words_per_row, |y, row| {
+            let above = self.extended_row(y as isize - 1);
+            let current = self.extended_row(y as isize);
+            let below = self.extended_row(y as isize + 1);
+
+            for (k, word) in row.iter_mut().enumerate() {
+                let (aw, ae) = self.shifted(&above, k);
+                let (w, e) = self.shifted(&current, k);
+                let (bw, be) = self.shifted(&below, k);
+                let alive = current.words[k];
+
+                let counts = Counts::of([aw, above.words[k], ae, w, e, bw, below.words[k], be]);
+                let births = born.iter().fold(0, |m, &n| m | counts.equal_to(n));
+                let survivals = survive.iter().fold(0, |m, &n| m | counts.equal_to(n));
+
+                *word = (births & !alive) | (survivals & alive);
+                if k == self.words_per_row - 1 {
+                    *word &= tail_mask;
+                }
+            }
+        });
+
+        self.words = next;
+    }
+}
+
+impl Universe for BitGrid {
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.on_board(x, y).is_some_and(|(x, y)| self.cell(x, y))
+    }
+
+    fn set(&mut self, x: i64, y: i64, is_alive: bool) {
+        if let Some((x, y)) = self.on_board(x, y) {
+            let word = &mut self.words[y * self.words_per_row + x / 64];
+            let bit = 1 << (x % 64);
+
+            if is_alive {
+                *word |= bit;
+            } else {
+                *word &= !bit;
+            }
+        }
+    }
+
+    fn step(&mut self, rule: &Rule) {
+        BitGrid::step(self, rule);
+    }
+
+    fn step_counting(&mut self, rule: &Rule) -> StepChanges {
+        let before = self.words.clone();
+        BitGrid::step(self, rule);
+
+        let mut changes = StepChanges::default();
+        for (&old, &new) in before.iter().zip(&self.words) {
+            changes.births += (new & !old).count_ones() as usize;
+            changes.deaths += (old & !new).count_ones() as usize;
+        }
+        changes
+    }
+
+    fn population(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+
+        for (i, &word) in self.words.iter().enumerate() {
+            let (y, k) = (i / self.words_per_row, i % self.words_per_row);
+            let mut bits = word;
+
+            while bits != 0 {
+                let x = 64 * k + bits.trailing_zeros() as usize;
+                cells.push((x as i64, y as i64));
+                bits &= bits - 1;
+            }
+        }
+
+        cells
+    }
+
+    fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    fn topology(&self) -> Option<Topology> {
+        Some(self.topology)
+    }
+
+    fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{grid::Grid, universe::sorted_cells};
+
+    #[test]
+    fn matches_grid_on_every_topology() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for width in [1, 2, 63, 64, 65, 130] {
+            for height in [1, 5, 33] {
+                for topology in Topology::ALL {
+                    // Life, and Day & Night, which uses most neighbour counts.
+                    for rule in [Rule::LIFE, Rule::PRESETS[2].1] {
+                        let mut grid = Grid::new(width, height, topology);
+                        let mut bits = BitGrid::new(width, height, topology);
+                        for y in 0..height {
+                            for x in 0..width {
+                                let is_alive = rng.gen_bool(0.35);
+                                grid.set(x, y, is_alive);
+                                Universe::set(&mut bits, x as i64, y as i64, is_alive);
+                            }
+                        }
+
+                        for generation in 1..=8 {
+                            grid.step(&rule);
+                            bits.step(&rule);
+                            assert_eq!(
+                                sorted_cells(&bits),
+                                sorted_cells(&grid),
+                                "{width}x{height} {topology:?} {rule} generation {generation}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
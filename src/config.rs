@@ -0,0 +1,159 @@
+//! Settings for a game, from the command line or a saved session.
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+
+use crate::{rule::Rule, topology::Topology, universe::Backend};
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub width: usize,
+    pub height: usize,
+    pub cell_size: f32,
+    pub initial_dencity: f64,
+    pub update_interval_millis: u64,
+    pub rule: Rule,
+    pub topology: Topology,
+    pub backend: Backend,
+    pub seed: Option<u64>,
+    pub pattern: Option<PathBuf>,
+    /// Directory scanned for extra patterns for the library.
+    #[serde(default = "default_library")]
+    pub library: PathBuf,
+    /// Generations kept for rewinding.
+    #[serde(default = "default_history_depth")]
+    pub history_depth: usize,
+    /// Snapshot file used by `--session`, Ctrl+S and Ctrl+L.
+    #[serde(skip)]
+    pub session: Option<PathBuf>,
+}
+
+pub const USAGE: &str = "usage: bevy-life-game [--seed N] [--rule B3/S23] [--grid T50,50] \
+[--backend dense|bitpacked|sparse|hashlife] [--pattern FILE] [--session FILE] [--history N] [--library DIR]";
+
+/// Largest side of the board. The board is drawn into a texture of the same
+/// size, which GPUs cap, and bounded backends keep every cell in memory;
+/// loaded patterns bigger than this switch to the sparse backend.
+pub const MAX_BOARD_SIZE: usize = 4096;
+
+pub const DEFAULT_SESSION: &str = "session.ron";
+
+pub const DEFAULT_HISTORY_DEPTH: usize = 1000;
+
+pub fn default_history_depth() -> usize {
+    DEFAULT_HISTORY_DEPTH
+}
+
+pub const DEFAULT_LIBRARY: &str = "patterns";
+
+pub fn default_library() -> PathBuf {
+    PathBuf::from(DEFAULT_LIBRARY)
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            width: 50,
+            height: 50,
+            cell_size: 10.0,
+            initial_dencity: 0.3,
+            update_interval_millis: 100,
+            rule: Rule::LIFE,
+            topology: Topology::Torus,
+            backend: Backend::Dense,
+            seed: None,
+            pattern: None,
+            library: default_library(),
+            history_depth: DEFAULT_HISTORY_DEPTH,
+            session: None,
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn window_width(&self) -> f32 {
+        self.width as f32 * self.cell_size
+    }
+
+    pub fn window_height(&self) -> f32 {
+        self.height as f32 * self.cell_size
+    }
+
+    pub fn session_path(&self) -> &Path {
+        self.session
+            .as_deref()
+            .unwrap_or(Path::new(DEFAULT_SESSION))
+    }
+
+    /// Overrides the defaults with `--seed`, `--rule`, `--grid` (a Golly
+    /// bounded-grid spec), `--backend`, `--pattern`, `--session`,
+    /// `--history` and `--library` command-line options.
+    pub fn apply_args(&mut self, mut args: impl Iterator<Item = String>) -> Result<(), String> {
+        while let Some(option) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{option} needs a value"))?;
+
+            match option.as_str() {
+                "--seed" => {
+                    let seed = value.parse().map_err(|e| format!("invalid seed: {e}"))?;
+                    self.seed = Some(seed);
+                }
+                "--rule" => {
+                    self.rule = value.parse().map_err(|e| format!("invalid rule: {e}"))?;
+                }
+                "--grid" => {
+                    let (topology, width, height) =
+                        Topology::from_golly(&value).map_err(|e| format!("invalid grid: {e}"))?;
+                    if width.max(height) > MAX_BOARD_SIZE {
+                        return Err(format!(
+                            "invalid grid: {width}x{height} is larger than {MAX_BOARD_SIZE} cells a side"
+                        ));
+                    }
+                    self.topology = topology;
+                    self.width = width;
+                    self.height = height;
+                }
+                "--backend" => {
+                    self.backend = value.parse().map_err(|e| format!("invalid backend: {e}"))?;
+                }
+                "--pattern" => self.pattern = Some(PathBuf::from(value)),
+                "--session" => self.session = Some(PathBuf::from(value)),
+                "--library" => self.library = PathBuf::from(value),
+                "--history" => {
+                    self.history_depth = value
+                        .parse()
+                        .map_err(|e| format!("invalid history depth: {e}"))?;
+                }
+                _ => return Err(format!("unknown option {option}")),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(args: &[&str]) -> Result<GameConfig, String> {
+        let mut config = GameConfig::default();
+        config.apply_args(args.iter().map(|arg| arg.to_string()))?;
+        Ok(config)
+    }
+
+    #[test]
+    fn applies_grid_sizes_up_to_the_cap() {
+        let config = apply(&["--grid", "K4096,20*"]).unwrap();
+        assert_eq!((config.width, config.height), (MAX_BOARD_SIZE, 20));
+    }
+
+    #[test]
+    fn rejects_grids_over_the_cap() {
+        assert!(apply(&["--grid", "T4097,20"]).is_err());
+        assert!(apply(&["--grid", "P20,5000"]).is_err());
+    }
+}
@@ -1,14 +1,242 @@
-use bevy::prelude::Component;
+use bevy::tasks::TaskPool;
 
-#[derive(Component, Clone, Copy, PartialEq, Eq)]
-pub struct GridCell {
-    pub x: usize,
-    pub y: usize,
-    pub is_alive: bool,
+use crate::{
+    parallel::{compute_pool, for_each_row},
+    rule::Rule,
+    topology::Topology,
+    universe::{StepChanges, Universe},
+};
+
+/// Dense, row-major Life board that can be stepped without a Bevy `App`.
+///
+/// The simulation never touches the ECS; the Bevy systems only read the
+/// grid to draw it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    cells: Vec<bool>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize, topology: Topology) -> Self {
+        Grid {
+            width,
+            height,
+            topology,
+            cells: vec![false; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn on_board(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        let x = usize::try_from(x).ok().filter(|&x| x < self.width)?;
+        let y = usize::try_from(y).ok().filter(|&y| y < self.height)?;
+        Some((x, y))
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "cell ({x}, {y}) is outside a {}x{} grid",
+            self.width,
+            self.height
+        );
+        y * self.width + x
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.cells[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, is_alive: bool) {
+        let i = self.index(x, y);
+        self.cells[i] = is_alive;
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|&&alive| alive).count()
+    }
+
+    pub fn step(&mut self, rule: &Rule) {
+        self.step_in(rule, compute_pool());
+    }
+
+    pub(crate) fn step_in(&mut self, rule: &Rule, pool: &TaskPool) {
+        let mut next = vec![false; self.cells.len()];
+
+        for_each_row(pool, &mut next, self.width, |y, row| {
+            for (x, cell) in row.iter_mut().enumerate() {
+                let i = y * self.width + x;
+                let neighbours = if self.is_interior(x, y) {
+                    self.interior_neighbours(i)
+                } else {
+                    self.border_neighbours(x, y)
+                };
+
+                *cell = rule.next_state(self.cells[i], neighbours);
+            }
+        });
+
+        self.cells = next;
+    }
+
+    fn is_interior(&self, x: usize, y: usize) -> bool {
+        x > 0 && y > 0 && x + 1 < self.width && y + 1 < self.height
+    }
+
+    /// Counts the neighbours of a cell that is not on the edge, where all
+    /// eight neighbours are plain offsets into `cells`.
+    fn interior_neighbours(&self, i: usize) -> u8 {
+        let w = self.width;
+
+        [i - w, i, i + w]
+            .into_iter()
+            .flat_map(|row| [row - 1, row, row + 1])
+            .filter(|&n| n != i && self.cells[n])
+            .count() as u8
+    }
+
+    fn border_neighbours(&self, x: usize, y: usize) -> u8 {
+        let mut count = 0;
+
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+
+                let neighbour = self.topology.resolve(
+                    x as isize + dx,
+                    y as isize + dy,
+                    self.width,
+                    self.height,
+                );
+
+                if neighbour.is_some_and(|(nx, ny)| self.get(nx, ny)) {
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+}
+
+impl Universe for Grid {
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.on_board(x, y)
+            .is_some_and(|(x, y)| Grid::get(self, x, y))
+    }
+
+    fn set(&mut self, x: i64, y: i64, is_alive: bool) {
+        if let Some((x, y)) = self.on_board(x, y) {
+            Grid::set(self, x, y, is_alive);
+        }
+    }
+
+    fn step(&mut self, rule: &Rule) {
+        Grid::step(self, rule);
+    }
+
+    fn step_counting(&mut self, rule: &Rule) -> StepChanges {
+        let before = self.cells.clone();
+        Grid::step(self, rule);
+
+        let mut changes = StepChanges::default();
+        for (&was_alive, &is_alive) in before.iter().zip(&self.cells) {
+            changes.births += usize::from(is_alive && !was_alive);
+            changes.deaths += usize::from(was_alive && !is_alive);
+        }
+        changes
+    }
+
+    fn population(&self) -> usize {
+        Grid::population(self)
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        (0..self.cells.len())
+            .filter(|&i| self.cells[i])
+            .map(|i| ((i % self.width) as i64, (i / self.width) as i64))
+            .collect()
+    }
+
+    fn clear(&mut self) {
+        self.cells.fill(false);
+    }
+
+    fn topology(&self) -> Option<Topology> {
+        Some(self.topology)
+    }
+
+    fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
 }
 
-impl GridCell {
-    pub fn new(x: usize, y: usize, is_alive: bool) -> Self {
-        GridCell { x, y, is_alive }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::sorted_cells;
+
+    fn grid_with(width: usize, height: usize, cells: &[(usize, usize)]) -> Grid {
+        let mut grid = Grid::new(width, height, Topology::Torus);
+        for &(x, y) in cells {
+            grid.set(x, y, true);
+        }
+        grid
+    }
+
+    #[test]
+    fn blinker_has_period_two() {
+        let mut grid = grid_with(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        let start = sorted_cells(&grid);
+
+        grid.step(&Rule::LIFE);
+        assert_eq!(sorted_cells(&grid), vec![(2, 1), (2, 2), (2, 3)]);
+
+        grid.step(&Rule::LIFE);
+        assert_eq!(sorted_cells(&grid), start);
+    }
+
+    #[test]
+    fn block_is_still() {
+        let mut grid = grid_with(4, 4, &[(1, 1), (2, 1), (1, 2), (2, 2)]);
+        let start = sorted_cells(&grid);
+
+        for _ in 0..5 {
+            grid.step(&Rule::LIFE);
+            assert_eq!(sorted_cells(&grid), start);
+        }
+    }
+
+    #[test]
+    fn glider_moves_across_the_torus() {
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        let mut grid = grid_with(8, 8, &glider);
+        let start = sorted_cells(&grid);
+
+        // A glider moves one cell down and right every four generations.
+        for _ in 0..4 {
+            grid.step(&Rule::LIFE);
+        }
+        let moved: Vec<_> = start.iter().map(|&(x, y)| (x + 1, y + 1)).collect();
+        assert_eq!(sorted_cells(&grid), moved);
+
+        // After 8 such moves it has wrapped all the way round.
+        for _ in 4..32 {
+            grid.step(&Rule::LIFE);
+        }
+        assert_eq!(sorted_cells(&grid), start);
+        assert_eq!(grid.population(), 5);
     }
 }
@@ -0,0 +1,602 @@
+use bevy::utils::HashMap;
+
+use crate::{
+    rule::Rule,
+    topology::Topology,
+    universe::{Bounds, StepChanges, Universe},
+};
+
+pub mod macrocell;
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Largest root level; a level-62 square still has `i64` coordinates.
+const MAX_LEVEL: u8 = 62;
+
+/// Node count above which unreachable nodes are dropped after a step.
+const GARBAGE_LIMIT: usize = 1 << 21;
+
+/// A square of `2^level` cells. Children are ordered
+/// `[low x low y, high x low y, low x high y, high x high y]`; the two
+/// level-0 nodes `DEAD` and `ALIVE` are single cells.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    level: u8,
+    children: [NodeId; 4],
+    population: u64,
+}
+
+/// Unbounded universe stored as a hash-consed quadtree with memoised
+/// futures (Gosper's HashLife), able to advance `2^k` generations at once.
+///
+/// The root is always centred on the origin. Like `SparseGrid`, births with
+/// zero neighbours (`B0`) are never considered.
+#[derive(Clone, Debug)]
+pub struct HashLife {
+    nodes: Vec<Node>,
+    ids: HashMap<[NodeId; 4], NodeId>,
+    empty: Vec<NodeId>,
+    results: HashMap<(NodeId, u32), NodeId>,
+    rule: Option<Rule>,
+    root: NodeId,
+}
+
+impl Default for HashLife {
+    fn default() -> Self {
+        HashLife::new()
+    }
+}
+
+impl HashLife {
+    pub fn new() -> Self {
+        let leaf = |population| Node {
+            level: 0,
+            children: [DEAD; 4],
+            population,
+        };
+
+        let mut hashlife = HashLife {
+            nodes: vec![leaf(0), leaf(1)],
+            ids: HashMap::default(),
+            empty: vec![DEAD],
+            results: HashMap::default(),
+            rule: None,
+            root: DEAD,
+        };
+        hashlife.root = hashlife.empty(3);
+        hashlife
+    }
+
+    fn level(&self) -> u8 {
+        self.nodes[self.root as usize].level
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    fn population_of(&self, id: NodeId) -> u64 {
+        self.nodes[id as usize].population
+    }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.ids.get(&children) {
+            return id;
+        }
+
+        let node = Node {
+            level: self.nodes[children[0] as usize].level + 1,
+            children,
+            population: children.iter().map(|&c| self.population_of(c)).sum(),
+        };
+
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(node);
+        self.ids.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let smaller = *self.empty.last().unwrap();
+            let node = self.join([smaller; 4]);
+            self.empty.push(node);
+        }
+
+        self.empty[level as usize]
+    }
+
+    /// The 4x4 grandchildren of a node, indexed `[y][x]`.
+    fn grandchildren(&self, id: NodeId) -> [[NodeId; 4]; 4] {
+        let mut grid = [[DEAD; 4]; 4];
+
+        for (q, child) in self.children(id).into_iter().enumerate() {
+            for (c, grandchild) in self.children(child).into_iter().enumerate() {
+                grid[2 * (q >> 1) + (c >> 1)][2 * (q & 1) + (c & 1)] = grandchild;
+            }
+        }
+
+        grid
+    }
+
+    /// The half-size square at the middle of a node.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let g = self.grandchildren(id);
+        self.join([g[1][1], g[1][2], g[2][1], g[2][2]])
+    }
+
+    /// Doubles the root's size, keeping its contents in the middle.
+    fn expand(&mut self) {
+        let level = self.level();
+        assert!(
+            level < MAX_LEVEL,
+            "HashLife universe outgrew i64 coordinates"
+        );
+
+        let e = self.empty(level - 1);
+        let [a, b, c, d] = self.children(self.root);
+        let children = [
+            self.join([e, e, e, a]),
+            self.join([e, e, b, e]),
+            self.join([e, c, e, e]),
+            self.join([d, e, e, e]),
+        ];
+        self.root = self.join(children);
+    }
+
+    /// Whether every live cell lies in the middle quarter of the root.
+    fn is_padded(&mut self) -> bool {
+        let centre = self.centre(self.root);
+        let inner = self.centre(centre);
+        self.population_of(inner) == self.population_of(self.root)
+    }
+
+    fn half(&self) -> i64 {
+        1 << (self.level() - 1)
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        let half = self.half();
+        (-half..half).contains(&x) && (-half..half).contains(&y)
+    }
+
+    fn set_in(&mut self, id: NodeId, level: u8, x: i64, y: i64, is_alive: bool) -> NodeId {
+        if level == 0 {
+            return if is_alive { ALIVE } else { DEAD };
+        }
+
+        let half = 1 << (level - 1);
+        let q = usize::from(x >= half) + 2 * usize::from(y >= half);
+        let mut children = self.children(id);
+        children[q] = self.set_in(children[q], level - 1, x % half, y % half, is_alive);
+        self.join(children)
+    }
+
+    fn collect(&self, id: NodeId, level: u8, x: i64, y: i64, cells: &mut Vec<(i64, i64)>) {
+        if self.population_of(id) == 0 {
+            return;
+        }
+
+        if level == 0 {
+            cells.push((x, y));
+            return;
+        }
+
+        let half = 1 << (level - 1);
+        for (q, child) in self.children(id).into_iter().enumerate() {
+            let (dx, dy) = ((q & 1) as i64 * half, (q >> 1) as i64 * half);
+            self.collect(child, level - 1, x + dx, y + dy, cells);
+        }
+    }
+
+    /// The lowest (or highest) coordinate of a live cell along one axis,
+    /// relative to the node's corner. Memoised per node because the
+    /// quadtree shares subtrees, so a huge but regular pattern stays cheap.
+    fn extent(
+        &self,
+        id: NodeId,
+        vertical: bool,
+        highest: bool,
+        memo: &mut HashMap<NodeId, Option<i64>>,
+    ) -> Option<i64> {
+        let node = self.nodes[id as usize];
+        if node.population == 0 {
+            return None;
+        }
+
+        if node.level == 0 {
+            return Some(0);
+        }
+
+        if let Some(&extent) = memo.get(&id) {
+            return extent;
+        }
+
+        let half = 1 << (node.level - 1);
+        let side = |q: usize| if vertical { q >> 1 } else { q & 1 };
+        let sides = if highest { [1, 0] } else { [0, 1] };
+
+        let extent = sides.into_iter().find_map(|wanted| {
+            let extents = (0..4)
+                .filter(|&q| side(q) == wanted)
+                .filter_map(|q| self.extent(node.children[q], vertical, highest, memo))
+                .map(|e| e + wanted as i64 * half);
+
+            if highest {
+                extents.max()
+            } else {
+                extents.min()
+            }
+        });
+
+        memo.insert(id, extent);
+        extent
+    }
+
+    /// One generation of the middle 2x2 of a 4x4 node.
+    fn step_leaves(&mut self, id: NodeId, rule: &Rule) -> NodeId {
+        let g = self.grandchildren(id);
+        let alive = |x: usize, y: usize| g[y][x] == ALIVE;
+
+        let mut next = [DEAD; 4];
+        for (i, cell) in next.iter_mut().enumerate() {
+            let (x, y) = (1 + (i & 1), 1 + (i >> 1));
+            let neighbours = (y - 1..=y + 1)
+                .flat_map(|ny| (x - 1..=x + 1).map(move |nx| (nx, ny)))
+                .filter(|&(nx, ny)| (nx, ny) != (x, y) && alive(nx, ny))
+                .count();
+
+            // A dead cell with no live neighbours stays dead, even under B0.
+            if (alive(x, y) || neighbours > 0) && rule.next_state(alive(x, y), neighbours as u8) {
+                *cell = ALIVE;
+            }
+        }
+
+        self.join(next)
+    }
+
+    /// The middle half of a node advanced `2^exponent` generations, where
+    /// `exponent` is at most the node's level minus two.
+    fn successor(&mut self, id: NodeId, exponent: u32, rule: &Rule) -> NodeId {
+        if let Some(&result) = self.results.get(&(id, exponent)) {
+            return result;
+        }
+
+        let level = self.nodes[id as usize].level;
+        let result = if self.population_of(id) == 0 {
+            self.empty(level - 1)
+        } else if level == 2 {
+            self.step_leaves(id, rule)
+        } else {
+            let g = self.grandchildren(id);
+            let full_speed = exponent == u32::from(level) - 2;
+            let first = if full_speed { exponent - 1 } else { exponent };
+
+            let mut partial = [[DEAD; 3]; 3];
+            for (i, row) in partial.iter_mut().enumerate() {
+                for (j, cell) in row.iter_mut().enumerate() {
+                    let overlap = self.join([g[i][j], g[i][j + 1], g[i + 1][j], g[i + 1][j + 1]]);
+                    *cell = self.successor(overlap, first, rule);
+                }
+            }
+
+            let mut quadrants = [DEAD; 4];
+            for (q, quadrant) in quadrants.iter_mut().enumerate() {
+                let (i, j) = (q >> 1, q & 1);
+                let combined = self.join([
+                    partial[i][j],
+                    partial[i][j + 1],
+                    partial[i + 1][j],
+                    partial[i + 1][j + 1],
+                ]);
+
+                *quadrant = if full_speed {
+                    self.successor(combined, exponent - 1, rule)
+                } else {
+                    self.centre(combined)
+                };
+            }
+
+            self.join(quadrants)
+        };
+
+        self.results.insert((id, exponent), result);
+        result
+    }
+
+    /// Live cells of `after` that are dead in `before`, two nodes of the
+    /// same level. Identical subtrees are skipped, so only the parts of the
+    /// universe that actually changed are visited.
+    fn births(
+        &self,
+        before: NodeId,
+        after: NodeId,
+        memo: &mut HashMap<(NodeId, NodeId), u64>,
+    ) -> u64 {
+        if before == after || self.population_of(after) == 0 {
+            return 0;
+        }
+
+        if self.population_of(before) == 0 {
+            return self.population_of(after);
+        }
+
+        if let Some(&births) = memo.get(&(before, after)) {
+            return births;
+        }
+
+        let births = self
+            .children(before)
+            .into_iter()
+            .zip(self.children(after))
+            .map(|(b, a)| self.births(b, a, memo))
+            .sum();
+
+        memo.insert((before, after), births);
+        births
+    }
+
+    /// Readies the root for `successor`: drops memoised futures of another
+    /// rule, and pads the root until its middle half holds every live cell
+    /// and it is big enough to advance `2^exponent` generations.
+    fn prepare(&mut self, rule: &Rule, exponent: u32) {
+        if self.rule != Some(*rule) {
+            self.results.clear();
+            self.rule = Some(*rule);
+        }
+
+        while u32::from(self.level()) < exponent + 3 || !self.is_padded() {
+            self.expand();
+        }
+    }
+
+    /// Rebuilds the node store from the root, dropping everything that is
+    /// no longer reachable along with the memoised futures.
+    fn collect_garbage(&mut self) {
+        let mut fresh = HashLife {
+            nodes: self.nodes[..2].to_vec(),
+            ids: HashMap::default(),
+            empty: vec![DEAD],
+            results: HashMap::default(),
+            rule: self.rule,
+            root: DEAD,
+        };
+
+        let mut copied = HashMap::default();
+        fresh.root = fresh.copy_from(self, self.root, &mut copied);
+        *self = fresh;
+    }
+
+    fn copy_from(
+        &mut self,
+        other: &HashLife,
+        id: NodeId,
+        copied: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if id == DEAD || id == ALIVE {
+            return id;
+        }
+
+        if let Some(&copy) = copied.get(&id) {
+            return copy;
+        }
+
+        let children = other
+            .children(id)
+            .map(|child| self.copy_from(other, child, copied));
+        let copy = self.join(children);
+        copied.insert(id, copy);
+        copy
+    }
+}
+
+impl Universe for HashLife {
+    fn get(&self, x: i64, y: i64) -> bool {
+        if !self.contains(x, y) {
+            return false;
+        }
+
+        let (mut x, mut y) = (x + self.half(), y + self.half());
+        let mut id = self.root;
+
+        for level in (1..=self.level()).rev() {
+            let half = 1 << (level - 1);
+            let q = usize::from(x >= half) + 2 * usize::from(y >= half);
+            id = self.children(id)[q];
+            x %= half;
+            y %= half;
+        }
+
+        id == ALIVE
+    }
+
+    fn set(&mut self, x: i64, y: i64, is_alive: bool) {
+        if !is_alive && !self.contains(x, y) {
+            return;
+        }
+
+        while !self.contains(x, y) {
+            self.expand();
+        }
+
+        let half = self.half();
+        self.root = self.set_in(self.root, self.level(), x + half, y + half, is_alive);
+    }
+
+    fn step(&mut self, rule: &Rule) {
+        self.step_pow2(rule, 0);
+    }
+
+    fn step_counting(&mut self, rule: &Rule) -> StepChanges {
+        self.prepare(rule, 0);
+
+        // The new root covers exactly the middle half of the old one.
+        let before = self.centre(self.root);
+        self.root = self.successor(self.root, 0, rule);
+
+        let births = self.births(before, self.root, &mut HashMap::default());
+        let deaths = self.population_of(before) + births - self.population_of(self.root);
+
+        if self.nodes.len() > GARBAGE_LIMIT {
+            self.collect_garbage();
+        }
+
+        StepChanges {
+            births: births as usize,
+            deaths: deaths as usize,
+        }
+    }
+
+    fn step_pow2(&mut self, rule: &Rule, exponent: u32) {
+        self.prepare(rule, exponent);
+        self.root = self.successor(self.root, exponent, rule);
+
+        if self.nodes.len() > GARBAGE_LIMIT {
+            self.collect_garbage();
+        }
+    }
+
+    fn population(&self) -> usize {
+        self.population_of(self.root) as usize
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::with_capacity(self.population());
+        let half = self.half();
+        self.collect(self.root, self.level(), -half, -half, &mut cells);
+        cells
+    }
+
+    fn clear(&mut self) {
+        self.root = self.empty(3);
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        let half = self.half();
+        let extent = |vertical, highest| {
+            self.extent(self.root, vertical, highest, &mut HashMap::default())
+                .map(|e| e - half)
+        };
+
+        Some(Bounds {
+            min_x: extent(false, false)?,
+            min_y: extent(true, false)?,
+            max_x: extent(false, true)?,
+            max_y: extent(true, true)?,
+        })
+    }
+
+    fn topology(&self) -> Option<Topology> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{grid::Grid, sparse::SparseGrid, topology::Topology, universe::sorted_cells};
+
+    /// A 16x16 random soup at (24, 24), far enough from the edges of a 64x64
+    /// grid that 16 generations never reach them.
+    fn soups(seed: u64) -> (HashLife, SparseGrid, Grid) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut hashlife = HashLife::new();
+        let mut sparse = SparseGrid::new();
+        let mut grid = Grid::new(64, 64, Topology::Plane);
+
+        for y in 24..40 {
+            for x in 24..40 {
+                let is_alive = rng.gen_bool(0.4);
+                hashlife.set(x, y, is_alive);
+                sparse.set(x, y, is_alive);
+                grid.set(x as usize, y as usize, is_alive);
+            }
+        }
+
+        (hashlife, sparse, grid)
+    }
+
+    #[test]
+    fn step_matches_the_other_backends() {
+        for (seed, (name, rule)) in Rule::PRESETS.into_iter().enumerate() {
+            let (mut hashlife, mut sparse, mut grid) = soups(seed as u64);
+
+            for generation in 1..=16 {
+                hashlife.step(&rule);
+                sparse.step(&rule);
+                grid.step(&rule);
+                assert_eq!(
+                    sorted_cells(&hashlife),
+                    sorted_cells(&sparse),
+                    "{name} generation {generation}"
+                );
+                assert_eq!(
+                    sorted_cells(&hashlife),
+                    sorted_cells(&grid),
+                    "{name} generation {generation}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn step_counting_matches_sparse_grid() {
+        for (seed, (name, rule)) in Rule::PRESETS.into_iter().enumerate() {
+            let (mut hashlife, mut sparse, _) = soups(seed as u64);
+
+            for generation in 1..=16 {
+                let changes = hashlife.step_counting(&rule);
+                assert_eq!(
+                    changes,
+                    sparse.step_counting(&rule),
+                    "{name} generation {generation}"
+                );
+                assert_eq!(
+                    sorted_cells(&hashlife),
+                    sorted_cells(&sparse),
+                    "{name} generation {generation}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn step_pow2_matches_single_steps() {
+        for (seed, (name, rule)) in Rule::PRESETS.into_iter().enumerate() {
+            let (mut hashlife, mut sparse, _) = soups(seed as u64);
+
+            for exponent in 0..=4 {
+                hashlife.step_pow2(&rule, exponent);
+                for _ in 0..1 << exponent {
+                    sparse.step(&rule);
+                }
+                assert_eq!(
+                    sorted_cells(&hashlife),
+                    sorted_cells(&sparse),
+                    "{name} 2^{exponent}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ignores_births_without_neighbours() {
+        let rule: Rule = "B03/S23".parse().unwrap();
+        let (mut hashlife, mut sparse, _) = soups(99);
+
+        for generation in 1..=8 {
+            hashlife.step(&rule);
+            sparse.step(&rule);
+            assert_eq!(
+                sorted_cells(&hashlife),
+                sorted_cells(&sparse),
+                "generation {generation}"
+            );
+        }
+    }
+}
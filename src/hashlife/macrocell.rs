@@ -0,0 +1,314 @@
+//! Golly Macrocell (`.mc`) files: the quadtree written out node by node,
+//! so that huge but regular patterns load straight into `HashLife`.
+//!
+//! Leaves are 8x8 squares written as rows of `.` and `*` ended by `$`;
+//! every other line is `level nw ne sw se`, where each child is the
+//! 1-based number of an earlier node line, or 0 for an empty square. The
+//! last node is the root, centred on the origin.
+
+use bevy::utils::HashMap;
+
+use super::{HashLife, NodeId, ALIVE, DEAD, MAX_LEVEL};
+use crate::{pattern::ParsePatternError, rule::Rule};
+
+pub const HEADER: &str = "[M2]";
+
+/// Level of the squares written as rows of cells.
+const LEAF_LEVEL: u8 = 3;
+
+/// A pattern read from a Macrocell file.
+#[derive(Clone, Debug, Default)]
+pub struct Macrocell {
+    pub universe: HashLife,
+    pub rule: Option<Rule>,
+    pub generation: u64,
+    pub comments: Vec<String>,
+}
+
+pub fn parse(text: &str) -> Result<Macrocell, ParsePatternError> {
+    if !text.trim_start().starts_with(HEADER) {
+        return Err(ParsePatternError::new(1, format!("expected \"{HEADER}\"")));
+    }
+
+    let mut macrocell = Macrocell::default();
+    let universe = &mut macrocell.universe;
+    let mut nodes = Vec::new();
+
+    for (number, line) in text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+    {
+        let error = |message: String| ParsePatternError::new(number, message);
+
+        if line.is_empty() || line.starts_with(HEADER) {
+            continue;
+        }
+
+        if let Some(directive) = line.strip_prefix('#') {
+            let mut chars = directive.chars();
+            let kind = chars.next();
+            let argument = chars.as_str().trim();
+
+            match kind {
+                Some('R') => {
+                    // Drop any bounded-grid suffix such as `:T100,100`.
+                    let rule = argument.split(':').next().unwrap_or_default();
+                    let rule = rule
+                        .parse()
+                        .map_err(|e| error(format!("invalid rule: {e}")))?;
+                    macrocell.rule = Some(rule);
+                }
+                Some('G') => {
+                    macrocell.generation = argument
+                        .parse()
+                        .map_err(|e| error(format!("invalid generation: {e}")))?;
+                }
+                Some('C' | 'D') => macrocell.comments.push(argument.to_string()),
+                _ => {}
+            }
+            continue;
+        }
+
+        let node = if line.starts_with(['.', '*', '$']) {
+            parse_leaf(universe, line)
+        } else {
+            parse_branch(universe, &nodes, line)
+        };
+        nodes.push(node.map_err(error)?);
+    }
+
+    if let Some(&root) = nodes.last() {
+        universe.root = root;
+        while universe.level() < 3 {
+            universe.expand();
+        }
+    }
+
+    Ok(macrocell)
+}
+
+fn parse_leaf(universe: &mut HashLife, line: &str) -> Result<NodeId, String> {
+    let mut cells = [[false; 8]; 8];
+    let (mut x, mut y) = (0, 0);
+
+    for c in line.chars() {
+        match c {
+            '$' => (x, y) = (0, y + 1),
+            '.' | '*' => {
+                if x >= 8 || y >= 8 {
+                    return Err("leaf does not fit in 8x8 cells".to_string());
+                }
+                cells[y][x] = c == '*';
+                x += 1;
+            }
+            c => return Err(format!("unexpected '{c}' in leaf")),
+        }
+    }
+
+    Ok(build_leaf(universe, &cells, 0, 0, LEAF_LEVEL))
+}
+
+fn build_leaf(
+    universe: &mut HashLife,
+    cells: &[[bool; 8]; 8],
+    x: usize,
+    y: usize,
+    level: u8,
+) -> NodeId {
+    if level == 0 {
+        return if cells[y][x] { ALIVE } else { DEAD };
+    }
+
+    let half = 1 << (level - 1);
+    let children = [(0, 0), (half, 0), (0, half), (half, half)]
+        .map(|(dx, dy)| build_leaf(universe, cells, x + dx, y + dy, level - 1));
+    universe.join(children)
+}
+
+fn parse_branch(universe: &mut HashLife, nodes: &[NodeId], line: &str) -> Result<NodeId, String> {
+    let fields: Result<Vec<usize>, _> = line.split_whitespace().map(str::parse).collect();
+    let Ok(&[level, nw, ne, sw, se]) = fields.as_deref() else {
+        return Err(format!("expected \"level nw ne sw se\", found \"{line}\""));
+    };
+
+    // Stepping grows the root by up to two levels, which must stay within
+    // `MAX_LEVEL`.
+    if level == 0 || level > usize::from(MAX_LEVEL - 2) {
+        return Err(format!("node level {level} is out of range"));
+    }
+    let level = level as u8;
+
+    let mut children = [DEAD; 4];
+    for (id, child) in children.iter_mut().zip([nw, ne, sw, se]) {
+        *id = if level == 1 {
+            // Multi-state files give level-1 children as cell states.
+            if child == 0 {
+                DEAD
+            } else {
+                ALIVE
+            }
+        } else if child == 0 {
+            universe.empty(level - 1)
+        } else {
+            let node = *nodes
+                .get(child - 1)
+                .ok_or_else(|| format!("node {child} is used before it is defined"))?;
+            if universe.nodes[node as usize].level != level - 1 {
+                return Err(format!("node {child} does not fit in a level-{level} node"));
+            }
+            node
+        };
+    }
+
+    Ok(universe.join(children))
+}
+
+/// Writes the universe as a Macrocell file, sharing every repeated
+/// subtree so that the file stays as small as the quadtree itself. The
+/// generation is only written when it is not zero.
+pub fn write(universe: &HashLife, rule: &Rule, generation: u64, comments: &[String]) -> String {
+    let mut out = format!("{HEADER} (bevy-life-game)\n#R {rule}\n");
+    if generation > 0 {
+        out.push_str(&format!("#G {generation}\n"));
+    }
+    for comment in comments {
+        out.push_str(&format!("#C {comment}\n"));
+    }
+
+    // Stepping can shrink the root below a leaf, which has no line of its
+    // own in the file.
+    let padded;
+    let universe = if universe.level() < LEAF_LEVEL {
+        let mut copy = universe.clone();
+        while copy.level() < LEAF_LEVEL {
+            copy.expand();
+        }
+        padded = copy;
+        &padded
+    } else {
+        universe
+    };
+
+    let mut lines = Vec::new();
+    write_node(universe, universe.root, &mut HashMap::default(), &mut lines);
+
+    for line in lines {
+        out.push_str(&line);
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Appends the lines of a node and its descendants, returning the node's
+/// number in the file.
+fn write_node(
+    universe: &HashLife,
+    id: NodeId,
+    numbers: &mut HashMap<NodeId, usize>,
+    lines: &mut Vec<String>,
+) -> usize {
+    if universe.population_of(id) == 0 {
+        return 0;
+    }
+
+    if let Some(&number) = numbers.get(&id) {
+        return number;
+    }
+
+    let node = universe.nodes[id as usize];
+    let line = if node.level == LEAF_LEVEL {
+        leaf_line(universe, id)
+    } else {
+        let [nw, ne, sw, se] = node
+            .children
+            .map(|child| write_node(universe, child, numbers, lines));
+        format!("{} {nw} {ne} {sw} {se}", node.level)
+    };
+
+    lines.push(line);
+    numbers.insert(id, lines.len());
+    lines.len()
+}
+
+fn leaf_line(universe: &HashLife, id: NodeId) -> String {
+    let mut live = Vec::new();
+    universe.collect(id, LEAF_LEVEL, 0, 0, &mut live);
+
+    let mut cells = [[false; 8]; 8];
+    for (x, y) in live {
+        cells[y as usize][x as usize] = true;
+    }
+
+    let rows = cells
+        .iter()
+        .rposition(|row| row.contains(&true))
+        .map_or(0, |y| y + 1);
+    let mut line = String::new();
+
+    for row in &cells[..rows] {
+        let length = row.iter().rposition(|&alive| alive).map_or(0, |x| x + 1);
+        line.extend(
+            row[..length]
+                .iter()
+                .map(|&alive| if alive { '*' } else { '.' }),
+        );
+        line.push('$');
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::{sorted_cells, Universe};
+
+    #[test]
+    fn round_trips_generation_and_cells() {
+        let mut universe = HashLife::new();
+        for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2), (-40, 17)] {
+            universe.set(x, y, true);
+        }
+
+        let text = write(&universe, &Rule::LIFE, 1234, &["glider".to_string()]);
+        assert!(text.contains("\n#G 1234\n"));
+
+        let macrocell = parse(&text).unwrap();
+        assert_eq!(macrocell.generation, 1234);
+        assert_eq!(macrocell.rule, Some(Rule::LIFE));
+        assert_eq!(macrocell.comments, ["glider"]);
+
+        assert_eq!(sorted_cells(&macrocell.universe), sorted_cells(&universe));
+    }
+
+    #[test]
+    fn round_trips_a_stepped_block() {
+        let mut universe = HashLife::new();
+        for (x, y) in [(-1, -1), (0, -1), (-1, 0), (0, 0)] {
+            universe.set(x, y, true);
+        }
+        universe.step(&Rule::LIFE);
+
+        let macrocell = parse(&write(&universe, &Rule::LIFE, 1, &[])).unwrap();
+        assert_eq!(
+            sorted_cells(&macrocell.universe),
+            [(-1, -1), (-1, 0), (0, -1), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn rejects_roots_too_big_to_step() {
+        let too_big = format!("{HEADER}\n{} 0 0 0 0\n", MAX_LEVEL - 1);
+        assert!(parse(&too_big).is_err());
+
+        // Chain levels 4..=MAX_LEVEL - 2 above the leaf, then step.
+        let mut text = format!("{HEADER}\n*$\n");
+        for level in 4..=MAX_LEVEL - 2 {
+            text.push_str(&format!("{level} {} 0 0 0\n", level - 3));
+        }
+        let mut macrocell = parse(&text).unwrap();
+        macrocell.universe.step(&Rule::LIFE);
+    }
+}
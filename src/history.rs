@@ -0,0 +1,310 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::Resource;
+
+use crate::universe::Universe;
+
+/// Most bytes of compressed frames kept; the oldest frames are dropped
+/// beyond this, and boards too big to fit are not recorded at all.
+const MAX_BYTES: usize = 256 << 20;
+
+/// Live cells recorded per generation, on average, while the simulation
+/// runs. Bigger boards are only recorded every few generations, so that
+/// recording costs a fraction of what stepping them does.
+const CELLS_PER_GENERATION: u64 = 1 << 18;
+
+/// One remembered generation.
+#[derive(Clone, Debug)]
+struct Frame {
+    generation: u64,
+    cells: Vec<u8>,
+}
+
+/// A bounded ring buffer of past generations, oldest first.
+///
+/// Frames are kept in increasing order of generation, and recording a
+/// generation forgets every frame from that generation on, as the board is
+/// about to take a different future. Each frame stores its live cells
+/// row by row as variable-length deltas, usually a byte or two per cell.
+///
+/// While the simulation runs, boards of more than `CELLS_PER_GENERATION`
+/// live cells are recorded every few generations rather than every one,
+/// so stepping back through them skips the generations in between.
+#[derive(Resource, Debug, Default)]
+pub struct History {
+    frames: VecDeque<Frame>,
+    depth: usize,
+    bytes: usize,
+}
+
+impl History {
+    /// A history keeping at most `depth` frames; zero disables it.
+    pub fn new(depth: usize) -> Self {
+        History {
+            depth,
+            ..Default::default()
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        self.trim();
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.bytes = 0;
+    }
+
+    /// Remembers `universe` as it is at `generation`.
+    pub fn record(&mut self, generation: u64, universe: &dyn Universe) {
+        self.forget_from(generation);
+
+        // Every cell takes at least a byte, so skip encoding boards that
+        // cannot fit. A frame that turns out too big once encoded is dropped
+        // too, rather than evicting every other frame and still not fitting.
+        if self.depth == 0 || universe.population() > MAX_BYTES {
+            return;
+        }
+
+        let cells = encode(universe.live_cells());
+        if cells.len() > MAX_BYTES {
+            return;
+        }
+        self.bytes += cells.len();
+        self.frames.push_back(Frame { generation, cells });
+        self.trim();
+    }
+
+    /// Remembers `universe` as the simulation steps on from `generation`,
+    /// unless the latest frame is too recent for a board of its size.
+    pub fn record_step(&mut self, generation: u64, universe: &dyn Universe) {
+        self.forget_from(generation);
+
+        let spacing = 1 + universe.population() as u64 / CELLS_PER_GENERATION;
+        let due = self
+            .frames
+            .back()
+            .is_none_or(|frame| generation - frame.generation >= spacing);
+        if due {
+            self.record(generation, universe);
+        }
+    }
+
+    /// Forgets every frame from `generation` on, for a board that is about
+    /// to take a different future without being recorded.
+    pub fn forget_from(&mut self, generation: u64) {
+        let kept = self.frames.partition_point(|f| f.generation < generation);
+        for frame in self.frames.drain(kept..) {
+            self.bytes -= frame.cells.len();
+        }
+    }
+
+    /// Index of the frame recorded at `generation`.
+    pub fn position(&self, generation: u64) -> Option<usize> {
+        let index = self.frames.partition_point(|f| f.generation < generation);
+        (self.generation(index)? == generation).then_some(index)
+    }
+
+    /// Index of the latest frame before `generation`.
+    pub fn before(&self, generation: u64) -> Option<usize> {
+        self.frames
+            .partition_point(|f| f.generation < generation)
+            .checked_sub(1)
+    }
+
+    /// Index of the earliest frame after `generation`.
+    pub fn after(&self, generation: u64) -> Option<usize> {
+        let index = self.frames.partition_point(|f| f.generation <= generation);
+        (index < self.frames.len()).then_some(index)
+    }
+
+    pub fn generation(&self, index: usize) -> Option<u64> {
+        self.frames.get(index).map(|frame| frame.generation)
+    }
+
+    /// Replaces the contents of `universe` with frame `index`, returning
+    /// its generation.
+    pub fn restore(&self, index: usize, universe: &mut dyn Universe) -> Option<u64> {
+        let frame = self.frames.get(index)?;
+        universe.clear();
+        for (x, y) in decode(&frame.cells) {
+            universe.set(x, y, true);
+        }
+        Some(frame.generation)
+    }
+
+    fn trim(&mut self) {
+        while self.frames.len() > self.depth || self.bytes > MAX_BYTES {
+            let Some(frame) = self.frames.pop_front() else {
+                break;
+            };
+            self.bytes -= frame.cells.len();
+        }
+    }
+}
+
+/// Packs cells as rows: the distance from the previous row, the number of
+/// cells, the first column relative to the previous row's, and then the gap
+/// before each further cell.
+fn encode(mut cells: Vec<(i64, i64)>) -> Vec<u8> {
+    cells.sort_unstable_by_key(|&(x, y)| (y, x));
+
+    let mut bytes = Vec::new();
+    let (mut last_y, mut last_x) = (0i64, 0i64);
+
+    for row in cells.chunk_by(|a, b| a.1 == b.1) {
+        let (x, y) = row[0];
+        write_varint(&mut bytes, zigzag(y.wrapping_sub(last_y)));
+        write_varint(&mut bytes, row.len() as u64);
+        write_varint(&mut bytes, zigzag(x.wrapping_sub(last_x)));
+
+        for pair in row.windows(2) {
+            write_varint(&mut bytes, pair[1].0.wrapping_sub(pair[0].0) as u64 - 1);
+        }
+        (last_x, last_y) = (x, y);
+    }
+
+    bytes
+}
+
+fn decode(mut bytes: &[u8]) -> Vec<(i64, i64)> {
+    let mut cells = Vec::new();
+    let (mut y, mut first_x) = (0i64, 0i64);
+
+    while !bytes.is_empty() {
+        y = y.wrapping_add(unzigzag(read_varint(&mut bytes)));
+        let count = read_varint(&mut bytes);
+        first_x = first_x.wrapping_add(unzigzag(read_varint(&mut bytes)));
+
+        let mut x = first_x;
+        cells.push((x, y));
+        for _ in 1..count {
+            x = x
+                .wrapping_add(read_varint(&mut bytes) as i64)
+                .wrapping_add(1);
+            cells.push((x, y));
+        }
+    }
+
+    cells
+}
+
+fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+fn unzigzag(n: u64) -> i64 {
+    (n >> 1) as i64 ^ -((n & 1) as i64)
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        bytes.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    bytes.push(n as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> u64 {
+    let mut n = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        n |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[i + 1..];
+            return n;
+        }
+    }
+    *bytes = &[];
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sparse::SparseGrid, universe::sorted_cells};
+
+    fn board(cells: &[(i64, i64)]) -> SparseGrid {
+        let mut board = SparseGrid::new();
+        for &(x, y) in cells {
+            board.set(x, y, true);
+        }
+        board
+    }
+
+    #[test]
+    fn restores_recorded_cells() {
+        let cells = [
+            (0, 0),
+            (5, 0),
+            (-3, 2),
+            (i64::MIN, i64::MAX),
+            (i64::MAX, i64::MIN),
+        ];
+        let mut history = History::new(10);
+        history.record(7, &board(&cells));
+
+        let mut restored = board(&[(1, 1)]);
+        assert_eq!(history.restore(0, &mut restored), Some(7));
+
+        let mut expected = cells.to_vec();
+        expected.sort();
+        assert_eq!(sorted_cells(&restored), expected);
+    }
+
+    #[test]
+    fn recording_forgets_the_future() {
+        let mut history = History::new(10);
+        for generation in 0..5 {
+            history.record_step(generation, &board(&[(generation as i64, 0)]));
+        }
+        assert_eq!(history.len(), 5);
+
+        history.record(2, &board(&[]));
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.before(2), Some(1));
+        assert_eq!(history.after(2), None);
+
+        history.forget_from(1);
+        assert_eq!(history.len(), 1);
+    }
+
+    #[test]
+    fn keeps_at_most_depth_frames() {
+        let mut history = History::new(3);
+        for generation in 0..10 {
+            history.record_step(generation, &board(&[(0, 0)]));
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.generation(0), Some(7));
+    }
+
+    #[test]
+    fn spaces_out_big_boards() {
+        let cells: Vec<_> = (0..2 * CELLS_PER_GENERATION as i64)
+            .map(|i| (i % 1000, i / 1000))
+            .collect();
+        let big = board(&cells);
+
+        let mut history = History::new(100);
+        for generation in 0..9 {
+            history.record_step(generation, &big);
+        }
+        let recorded: Vec<_> = (0..history.len())
+            .filter_map(|i| history.generation(i))
+            .collect();
+        assert_eq!(recorded, [0, 3, 6]);
+    }
+}
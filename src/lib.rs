@@ -0,0 +1,15 @@
+pub mod bitgrid;
+pub mod config;
+pub mod grid;
+pub mod hashlife;
+pub mod history;
+pub mod library;
+mod parallel;
+pub mod pattern;
+pub mod rule;
+pub mod selection;
+pub mod session;
+pub mod sparse;
+pub mod topology;
+pub mod undo;
+pub mod universe;
@@ -0,0 +1,209 @@
+//! Patterns to stamp onto the board: a built-in set of classics, plus any
+//! pattern files found in a user directory.
+
+use std::{fs, path::Path};
+
+use crate::{
+    hashlife::macrocell,
+    pattern::{self, Format, ParsePatternError, Pattern},
+    universe::Universe,
+};
+
+/// The built-in patterns, as RLE.
+const BUILTIN: [&str; 19] = [
+    "#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!",
+    "#N LWSS\nx = 5, y = 4, rule = B3/S23\nbo2bo$o4b$o3bo$4o!",
+    "#N MWSS\nx = 6, y = 5, rule = B3/S23\n3bo2b$bo3bo$o5b$o4bo$5o!",
+    "#N HWSS\nx = 7, y = 5, rule = B3/S23\n3b2o2b$bo4bo$o6b$o5bo$6o!",
+    "#N Block\nx = 2, y = 2, rule = B3/S23\n2o$2o!",
+    "#N Beehive\nx = 4, y = 3, rule = B3/S23\nb2o$o2bo$b2o!",
+    "#N Loaf\nx = 4, y = 4, rule = B3/S23\nb2o$o2bo$bobo$2bo!",
+    "#N Boat\nx = 3, y = 3, rule = B3/S23\n2o$obo$bo!",
+    "#N Blinker\nx = 3, y = 1, rule = B3/S23\n3o!",
+    "#N Toad\nx = 4, y = 2, rule = B3/S23\nb3o$3o!",
+    "#N Beacon\nx = 4, y = 4, rule = B3/S23\n2o2b$2o2b$2b2o$2b2o!",
+    "#N Pulsar\nx = 13, y = 13, rule = B3/S23\n\
+     2b3o3b3o2b2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2b2$2b3o3b3o2b$\
+     o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    "#N Pentadecathlon\nx = 10, y = 3, rule = B3/S23\n2bo4bo2b$2ob4ob2o$2bo4bo!",
+    "#N Gosper glider gun\nx = 36, y = 9, rule = B3/S23\n\
+     24bo11b$22bobo11b$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o14b$\
+     2o8bo3bob2o4bobo11b$10bo5bo7bo11b$11bo3bo20b$12b2o!",
+    "#N Simkin glider gun\nx = 33, y = 21, rule = B3/S23\n\
+     2o5b2o$2o5b2o2$4b2o$4b2o5$22b2ob2o$21bo5bo$21bo6bo2b2o$21b3o3bo3b2o$\
+     26bo4$20b2o$20bo$21b3o$23bo!",
+    "#N R-pentomino\nx = 3, y = 3, rule = B3/S23\nb2o$2o$bo!",
+    "#N Acorn\nx = 7, y = 3, rule = B3/S23\nbo5b$3bo3b$2o2b3o!",
+    "#N Diehard\nx = 8, y = 3, rule = B3/S23\n6bob$2o6b$bo3b3o!",
+    "#N Copperhead\nx = 8, y = 12, rule = B3/S23\n\
+     b2o2b2o$3b2o$3b2o$obo2bobo$o6bo2$o6bo$b2o2b2o$2b4o2$3b2o$3b2o!",
+];
+
+/// File extensions `scan` picks up.
+const EXTENSIONS: [&str; 6] = ["rle", "cells", "lif", "life", "mc", "txt"];
+
+/// Largest population of a Macrocell file `scan` lists. Library entries
+/// are flat lists of cells, which a breeder or other huge quadtree would
+/// take forever to expand into; such files can still be loaded directly.
+const MAX_MACROCELL_CELLS: usize = 1 << 20;
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub pattern: Pattern,
+}
+
+pub fn builtin() -> Vec<Entry> {
+    BUILTIN
+        .iter()
+        .map(|text| {
+            let pattern = pattern::parse(text).expect("built-in patterns are valid RLE");
+            Entry {
+                name: pattern.name.clone().unwrap_or_default(),
+                pattern,
+            }
+        })
+        .collect()
+}
+
+/// Reads every pattern file in `dir` and its subdirectories, sorted by
+/// name. Files that cannot be read are reported in the returned errors; a
+/// missing directory is not an error.
+pub fn scan(dir: &Path) -> (Vec<Entry>, Vec<String>) {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let listing = match fs::read_dir(&dir) {
+            Ok(listing) => listing,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                errors.push(format!("{}: {e}", dir.display()));
+                continue;
+            }
+        };
+
+        for path in listing.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            if !EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()) {
+                continue;
+            }
+
+            let text = match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) => {
+                    errors.push(format!("{}: {e}", path.display()));
+                    continue;
+                }
+            };
+
+            match parse(&text) {
+                Ok(pattern) if !pattern.cells.is_empty() => {
+                    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                    entries.push(Entry {
+                        name: pattern.name.clone().unwrap_or_else(|| stem.into_owned()),
+                        pattern,
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => errors.push(format!("{}: {e}", path.display())),
+            }
+        }
+    }
+
+    entries.sort_by_key(|entry| entry.name.to_lowercase());
+    (entries, errors)
+}
+
+fn parse(text: &str) -> Result<Pattern, ParsePatternError> {
+    if Format::detect(text) != Format::Macrocell {
+        return pattern::parse(text);
+    }
+
+    let macrocell = macrocell::parse(text)?;
+    let population = macrocell.universe.population();
+    if population > MAX_MACROCELL_CELLS {
+        return Err(ParsePatternError::new(
+            0,
+            format!("{population} cells are too many for the library"),
+        ));
+    }
+    Ok(Pattern::from(macrocell))
+}
+
+/// Whether `name` contains every word of `query`, ignoring case.
+pub fn matches(name: &str, query: &str) -> bool {
+    let name = name.to_lowercase();
+    query
+        .split_whitespace()
+        .all(|word| name.contains(&word.to_lowercase()))
+}
+
+/// A `width` x `height` raster of `pattern`, at most `max_size` pixels on
+/// its longer side, where a pixel is set if any cell it covers is alive.
+/// Cells outside the pattern's bounds are left out.
+pub fn thumbnail(pattern: &Pattern, max_size: u64) -> (u64, u64, Vec<bool>) {
+    let longest = pattern.width.max(pattern.height).max(1);
+    let scale = longest.div_ceil(max_size.max(1));
+    let width = pattern.width.div_ceil(scale).max(1);
+    let height = pattern.height.div_ceil(scale).max(1);
+
+    let mut pixels = vec![false; (width * height) as usize];
+    for &(x, y) in &pattern.cells {
+        let (Ok(x), Ok(y)) = (u64::try_from(x), u64::try_from(y)) else {
+            continue;
+        };
+        let (x, y) = (x / scale, y / scale);
+        if x < width && y < height {
+            pixels[(y * width + x) as usize] = true;
+        }
+    }
+
+    (width, height, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_patterns_parse() {
+        assert_eq!(builtin().len(), BUILTIN.len());
+    }
+
+    #[test]
+    fn thumbnail_skips_cells_outside_the_pattern() {
+        let pattern = Pattern {
+            width: 2,
+            height: 2,
+            cells: vec![(0, 0), (1, 1), (2, 0), (-1, 5), (0, 9)],
+            ..Pattern::default()
+        };
+        assert_eq!(
+            thumbnail(&pattern, 48),
+            (2, 2, vec![true, false, false, true])
+        );
+    }
+
+    #[test]
+    fn rejects_huge_macrocell_files() {
+        // Full squares from level 4 (256 cells) up to level 11 (4M cells).
+        let mut text = format!("{}\n", macrocell::HEADER);
+        text.push_str(&"********$".repeat(8));
+        text.push_str("\n4 1 1 1 1\n");
+        for level in 5..=11 {
+            let child = level - 3;
+            text.push_str(&format!("{level} {child} {child} {child} {child}\n"));
+        }
+        assert!(parse(&text).is_err());
+
+        let small = format!("{}\n*$\n4 1 0 0 0\n", macrocell::HEADER);
+        assert_eq!(parse(&small).unwrap().cells.len(), 1);
+    }
+}
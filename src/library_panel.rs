@@ -0,0 +1,339 @@
+//! The pattern library panel: a searchable list of patterns with
+//! thumbnails, opened with Tab, whose entries are picked up for stamping.
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::ImageSampler,
+    },
+};
+use bevy_life_game::{
+    config::GameConfig,
+    library::{self, Entry},
+    pattern::Pattern,
+};
+
+use crate::{selection_tools::Paste, GameSet};
+
+/// Side of the box library thumbnails are fitted into, in pixels.
+const THUMBNAIL_SIZE: f32 = 48.0;
+
+/// Most library entries listed at once; searching narrows the rest down.
+const MAX_LIBRARY_ROWS: usize = 8;
+
+/// The pattern library, with a thumbnail for each entry, and the state of
+/// its panel.
+#[derive(Resource, Default)]
+struct PatternLibrary {
+    entries: Vec<(Entry, Handle<Image>)>,
+    open: bool,
+    query: String,
+}
+
+impl PatternLibrary {
+    fn matches(&self) -> impl Iterator<Item = (usize, &(Entry, Handle<Image>))> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (entry, _))| library::matches(&entry.name, &self.query))
+    }
+}
+
+#[derive(Component)]
+struct LibraryPanel;
+
+#[derive(Component)]
+struct LibrarySearch;
+
+#[derive(Component)]
+struct LibraryList;
+
+/// A library entry in the panel, by index.
+#[derive(Component)]
+struct LibraryRow(usize);
+
+pub(crate) struct LibraryPanelPlugin;
+
+impl Plugin for LibraryPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, (setup_library, spawn_library_panel))
+            .add_systems(
+                Update,
+                // The panel takes the keyboard before any shortcut sees it.
+                (control_library, update_library_panel)
+                    .chain()
+                    .before(GameSet::Controls),
+            );
+    }
+}
+
+/// Loads the built-in patterns and those in the library directory, and
+/// draws their thumbnails.
+fn setup_library(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    config: Res<GameConfig>,
+) {
+    let (extra, errors) = library::scan(&config.library);
+    for error in errors {
+        warn!("library: {error}");
+    }
+    if !extra.is_empty() {
+        info!(
+            "loaded {} patterns from {}",
+            extra.len(),
+            config.library.display()
+        );
+    }
+
+    let entries = library::builtin()
+        .into_iter()
+        .chain(extra)
+        .map(|entry| {
+            let thumbnail = images.add(thumbnail_image(&entry.pattern));
+            (entry, thumbnail)
+        })
+        .collect();
+
+    commands.insert_resource(PatternLibrary {
+        entries,
+        ..default()
+    });
+}
+
+fn thumbnail_image(pattern: &Pattern) -> Image {
+    let (width, height, pixels) = library::thumbnail(pattern, THUMBNAIL_SIZE as u64);
+    let data = pixels
+        .into_iter()
+        .flat_map(|alive| if alive { [255; 4] } else { [0, 0, 0, 255] })
+        .collect();
+
+    let mut image = Image::new(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+/// Opens and closes the library panel on Tab. While it is open it takes all
+/// typing as the search, Enter or a click picks an entry to stamp, and
+/// Escape clears the search or closes the panel.
+fn control_library(
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    mut typed: EventReader<KeyboardInput>,
+    rows: Query<(&Interaction, &LibraryRow), Changed<Interaction>>,
+    mut library: ResMut<PatternLibrary>,
+    mut paste: ResMut<Paste>,
+) {
+    if keyboard.just_pressed(KeyCode::Tab) {
+        library.open = !library.open;
+    }
+
+    if !library.open {
+        typed.clear();
+        return;
+    }
+
+    let mut picked = rows
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, LibraryRow(index))| *index);
+
+    for event in typed.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Character(text) => library.query.push_str(text),
+            Key::Space => library.query.push(' '),
+            Key::Backspace => {
+                library.query.pop();
+            }
+            Key::Escape if library.query.is_empty() => library.open = false,
+            Key::Escape => library.query.clear(),
+            Key::Enter => picked = library.matches().next().map(|(index, _)| index),
+            _ => {}
+        }
+    }
+
+    if let Some((entry, _)) = picked.and_then(|index| library.entries.get(index)) {
+        info!("stamping {}", entry.name);
+        paste.pattern = Some(entry.pattern.clone());
+        paste.repeat = true;
+        library.open = false;
+    }
+
+    // Keep the typing away from the keyboard shortcuts.
+    keyboard.reset_all();
+}
+
+fn update_library_panel(
+    mut commands: Commands,
+    library: Res<PatternLibrary>,
+    mut panels: Query<&mut Visibility, With<LibraryPanel>>,
+    mut searches: Query<&mut Text, With<LibrarySearch>>,
+    lists: Query<Entity, With<LibraryList>>,
+) {
+    if !library.is_changed() {
+        return;
+    }
+
+    for mut visibility in panels.iter_mut() {
+        *visibility = if library.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    for mut text in searches.iter_mut() {
+        text.sections[0].value = format!("search: {}_", library.query);
+    }
+
+    let matches: Vec<_> = library.matches().collect();
+    for list in &lists {
+        commands.entity(list).despawn_descendants();
+        commands.entity(list).with_children(|parent| {
+            for &(index, (entry, thumbnail)) in matches.iter().take(MAX_LIBRARY_ROWS) {
+                spawn_library_row(parent, index, entry, thumbnail);
+            }
+
+            if matches.len() > MAX_LIBRARY_ROWS {
+                let more = format!("{} more", matches.len() - MAX_LIBRARY_ROWS);
+                parent.spawn(TextBundle::from_section(
+                    more,
+                    TextStyle {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                ));
+            }
+        });
+    }
+}
+
+fn spawn_library_row(
+    parent: &mut ChildBuilder,
+    index: usize,
+    entry: &Entry,
+    thumbnail: &Handle<Image>,
+) {
+    let pattern = &entry.pattern;
+    let longest = pattern.width.max(pattern.height).max(1) as f32;
+
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Px(THUMBNAIL_SIZE + 8.0),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    margin: UiRect::bottom(Val::Px(2.0)),
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::srgb(0.15, 0.15, 0.15).into(),
+                ..default()
+            },
+            LibraryRow(index),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(THUMBNAIL_SIZE),
+                        height: Val::Px(THUMBNAIL_SIZE),
+                        flex_shrink: 0.0,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(ImageBundle {
+                        style: Style {
+                            width: Val::Px(THUMBNAIL_SIZE * pattern.width as f32 / longest),
+                            height: Val::Px(THUMBNAIL_SIZE * pattern.height as f32 / longest),
+                            ..default()
+                        },
+                        image: UiImage::new(thumbnail.clone()),
+                        ..default()
+                    });
+                });
+
+            parent.spawn(TextBundle::from_section(
+                format!("{}\n{}x{}", entry.name, pattern.width, pattern.height),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn spawn_library_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(30.0),
+                    right: Val::Px(5.0),
+                    width: Val::Px(240.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(1),
+                ..default()
+            },
+            // Keeps clicks on the panel from painting the board below.
+            Interaction::default(),
+            LibraryPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "search: _",
+                    TextStyle {
+                        font_size: 18.0,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(6.0)),
+                    ..default()
+                }),
+                LibrarySearch,
+            ));
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    },
+                    ..default()
+                },
+                LibraryList,
+            ));
+        });
+}
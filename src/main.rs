@@ -1,161 +1,1403 @@
-use std::time::Duration;
+use std::{
+    any::Any,
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use bevy::{
     app::{App, Startup},
+    input::mouse::{MouseScrollUnit, MouseWheel},
     prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::ImageSampler,
+    },
     time::{Timer, TimerMode},
+    ui::RelativeCursorPosition,
+    window::{FileDragAndDrop, PrimaryWindow},
     DefaultPlugins,
 };
-use grid::GridCell;
-use rand::Rng;
+use bevy_life_game::{
+    config::{GameConfig, MAX_BOARD_SIZE, USAGE},
+    hashlife::{
+        macrocell::{self, Macrocell},
+        HashLife,
+    },
+    history::History,
+    pattern::{self, rle, Format, Pattern},
+    rule::Rule,
+    selection,
+    session::Snapshot,
+    undo::{Edit, UndoLog},
+    universe::{Backend, Bounds, StepChanges, Universe},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use library_panel::LibraryPanelPlugin;
+use selection_tools::{Paste, Selection, SelectionToolsPlugin};
+
+mod library_panel;
+mod selection_tools;
+
+/// The stages of a frame, in order: keyboard controls, then edits to the
+/// board, then stepping and drawing it.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum GameSet {
+    Controls,
+    Edit,
+    Advance,
+}
+
+#[derive(Resource)]
+struct GridUpdateTimer(Timer);
+
+#[derive(Resource, Deref, DerefMut)]
+struct Board(Box<dyn Universe>);
+
+/// Seed of the random soup currently on the board.
+#[derive(Resource)]
+struct Seed(u64);
+
+/// What the simulation has been doing, as shown in the HUD.
+#[derive(Resource, Default)]
+struct SimulationStats {
+    /// Generations simulated since the board was last reset or loaded.
+    generation: u64,
+    population: usize,
+    /// Births and deaths in the latest step, or `None` after a jump.
+    last_step: Option<StepChanges>,
+    /// Generations per second, measured over the last half second.
+    generations_per_second: f64,
+}
+
+/// Live cells of the last pattern, Macrocell file or session loaded, or of
+/// the starting soup before any load, which the restore reset mode brings
+/// back. Random resets leave it alone.
+#[derive(Resource, Default)]
+struct LastLoaded(Vec<(i64, i64)>);
+
+/// Largest power-of-two jump, far beyond anything a glider gun needs.
+const MAX_JUMP_EXPONENT: u32 = 48;
+
+/// Power-of-two generation jumps. In hyperspeed every tick advances
+/// `2^exponent` generations and then doubles the step.
+#[derive(Resource, Default)]
+struct Jump {
+    exponent: u32,
+    hyperspeed: bool,
+}
+
+/// Slowest generation interval the speed controls go down to.
+const MAX_INTERVAL_MILLIS: u64 = 4000;
+
+/// Largest number of generations a single step-N can queue.
+const MAX_STEP_SIZE: u64 = 1_000_000;
+
+/// Time each frame may spend stepping the simulation, so that high speeds
+/// and long step-N runs never stall rendering or input.
+const STEP_BUDGET: Duration = Duration::from_millis(12);
+
+/// Camera scales allowed by zooming: 64x magnified down to 4x shrunk.
+const MIN_CAMERA_SCALE: f32 = 1.0 / 64.0;
+const MAX_CAMERA_SCALE: f32 = 4.0;
+
+/// Zoom factor per mouse-wheel notch.
+const ZOOM_STEP: f32 = 1.15;
+
+/// Arrow-key panning speed, in screen pixels per second.
+const PAN_SPEED: f32 = 500.0;
+
+/// Whether the simulation runs on its own, and generations queued by the
+/// step controls, which run even while paused.
+#[derive(Resource)]
+struct Playback {
+    paused: bool,
+    step_size: u64,
+    queued: u64,
+}
 
-mod grid;
+impl Default for Playback {
+    fn default() -> Self {
+        Playback {
+            paused: false,
+            step_size: 10,
+            queued: 0,
+        }
+    }
+}
 
-#[derive(Resource, Clone, Copy, Debug)]
-struct GameConfig {
-    width: usize,
-    height: usize,
-    cell_size: f32,
-    initial_dencity: f64,
-    update_interval_millis: u64,
+/// A pattern read from disk. Macrocell files stay quadtrees so that huge
+/// patterns are never expanded onto a bounded board.
+enum PatternFile {
+    Cells(Pattern),
+    Quadtree(Macrocell),
 }
 
-impl GameConfig {
-    pub fn window_width(&self) -> f32 {
-        self.width as f32 * self.cell_size
+/// A pattern waiting to replace the contents of the board.
+#[derive(Resource, Default)]
+struct PendingPattern(Option<PatternFile>);
+
+/// A snapshot waiting to replace the whole session.
+#[derive(Resource, Default)]
+struct PendingSnapshot(Option<(Snapshot, Box<dyn Universe>)>);
+
+/// Universe coordinate shown in the top-left corner of the board image.
+#[derive(Resource, Default)]
+struct Viewport {
+    x: i64,
+    y: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ResetMode {
+    Random,
+    Clear,
+    Restore,
+}
+
+impl ResetMode {
+    fn label(&self) -> &'static str {
+        match self {
+            ResetMode::Random => "Random",
+            ResetMode::Clear => "Clear",
+            ResetMode::Restore => "Restore",
+        }
     }
+}
 
-    pub fn window_height(&self) -> f32 {
-        self.height as f32 * self.cell_size
+#[derive(Component)]
+struct ResetButton(ResetMode);
+
+#[derive(Component)]
+struct SeedText;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PlaybackAction {
+    PlayPause,
+    Step,
+    StepMany,
+    Slower,
+    Faster,
+}
+
+impl PlaybackAction {
+    fn label(&self, playback: &Playback) -> String {
+        match self {
+            PlaybackAction::PlayPause if playback.paused => String::from("Play"),
+            PlaybackAction::PlayPause => String::from("Pause"),
+            PlaybackAction::Step => String::from("Step"),
+            PlaybackAction::StepMany => format!("Step {}", playback.step_size),
+            PlaybackAction::Slower => String::from("Slower"),
+            PlaybackAction::Faster => String::from("Faster"),
+        }
     }
 }
 
-#[derive(Resource)]
-struct GridUpdateTimer(Timer);
+#[derive(Component)]
+struct PlaybackButton(PlaybackAction);
+
+/// Text of a playback button, relabelled as the playback state changes.
+#[derive(Component)]
+struct PlaybackLabel(PlaybackAction);
 
 #[derive(Component)]
-struct ResetButton;
+struct SpeedText;
 
-fn spawn_grid(commands: &mut Commands, config: &Res<GameConfig>) {
-    let offset = Vec3::new(
-        -1.0 * (config.window_width() - config.cell_size) / 2.0,
-        -1.0 * (config.window_height() - config.cell_size) / 2.0,
-        0.0,
+#[derive(Component)]
+struct HudText;
+
+/// The sprite the board is drawn on.
+#[derive(Component)]
+struct BoardImage;
+
+const ALIVE_TEXEL: [u8; 4] = [255, 255, 255, 255];
+const DEAD_TEXEL: [u8; 4] = [0, 0, 0, 255];
+
+/// The history bar, which rewinds to the generation under the cursor while
+/// it is held.
+#[derive(Component)]
+struct Scrubber;
+
+/// Marks the current generation on the history bar.
+#[derive(Component)]
+struct ScrubberHandle;
+
+/// A mouse stroke in progress, remembering the last cell painted so that
+/// fast strokes can be joined up without gaps.
+#[derive(Default)]
+struct Stroke {
+    active: bool,
+    last: Option<(i64, i64)>,
+}
+
+/// Spawns the sprite showing the visible part of the board, drawn into an
+/// image with one texel per cell and scaled up without smoothing.
+fn spawn_board(commands: &mut Commands, images: &mut Assets<Image>, config: &GameConfig) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: config.width as u32,
+            height: config.height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &DEAD_TEXEL,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
     );
+    image.sampler = ImageSampler::nearest();
 
-    let mut rng = rand::thread_rng();
-
-    for x in 0..config.width {
-        for y in 0..config.height {
-            let position = Vec3::new(
-                x as f32 * config.cell_size,
-                y as f32 * config.cell_size,
-                0.0,
-            ) + offset;
-
-            commands.spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        color: Color::BLACK,
-                        custom_size: Some(Vec2::new(config.cell_size, config.cell_size)),
-                        ..Default::default()
-                    },
-                    transform: Transform::from_translation(position),
-                    ..Default::default()
-                },
-                GridCell::new(x, y, rng.gen_bool(config.initial_dencity)),
-            ));
+    // Texture row 0 is drawn at the top, matching pattern files.
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(config.window_width(), config.window_height())),
+                ..default()
+            },
+            texture: images.add(image),
+            ..default()
+        },
+        BoardImage,
+    ));
+}
+
+/// The board image coordinates under the cursor, inverting the placement
+/// in `spawn_board`.
+fn cursor_cell(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    config: &GameConfig,
+) -> Option<(usize, usize)> {
+    let cursor = window.cursor_position()?;
+    let world = camera.viewport_to_world_2d(camera_transform, cursor)?;
+
+    let x = ((world.x + config.window_width() / 2.0) / config.cell_size).floor();
+    let row = ((world.y + config.window_height() / 2.0) / config.cell_size).floor();
+    let y = config.height as f32 - 1.0 - row;
+
+    let on_grid =
+        (0.0..config.width as f32).contains(&x) && (0.0..config.height as f32).contains(&y);
+    on_grid.then_some((x as usize, y as usize))
+}
+
+/// World position of the centre of universe cell `(x, y)`, matching the
+/// placement in `spawn_board`.
+fn cell_center(x: i64, y: i64, viewport: &Viewport, config: &GameConfig) -> Vec2 {
+    let (column, row) = ((x - viewport.x) as f32, (y - viewport.y) as f32);
+    Vec2::new(
+        column * config.cell_size - (config.window_width() - config.cell_size) / 2.0,
+        (config.height as f32 - 1.0 - row) * config.cell_size
+            - (config.window_height() - config.cell_size) / 2.0,
+    )
+}
+
+/// Centre and size in the world of the cells in `area`.
+fn area_rect(area: &Bounds, viewport: &Viewport, config: &GameConfig) -> (Vec2, Vec2) {
+    let top_left = cell_center(area.min_x, area.min_y, viewport, config);
+    let bottom_right = cell_center(area.max_x, area.max_y, viewport, config);
+    let size = (bottom_right - top_left).abs() + Vec2::splat(config.cell_size);
+    ((top_left + bottom_right) / 2.0, size)
+}
+
+/// The cells on the straight line from `from` to `to`, both included.
+fn line_cells(from: (i64, i64), to: (i64, i64)) -> Vec<(i64, i64)> {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+    let mut cells = vec![from];
+
+    while (x, y) != to {
+        if 2 * error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if 2 * error <= dx {
+            error += dx;
+            y += step_y;
+        }
+        cells.push((x, y));
+    }
+
+    cells
+}
+
+/// Fills the visible `width` x `height` area with a soup that depends only
+/// on `seed` and the configured density.
+fn fill_random_soup(universe: &mut dyn Universe, config: &GameConfig, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for y in 0..config.height as i64 {
+        for x in 0..config.width as i64 {
+            universe.set(x, y, rng.gen_bool(config.initial_dencity));
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn reset_game(
-    mut commands: Commands,
-    query: Query<Entity, With<GridCell>>,
-    mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<ResetButton>)>,
+    mut board: ResMut<Board>,
+    mut viewport: ResMut<Viewport>,
+    mut seed: ResMut<Seed>,
+    mut stats: ResMut<SimulationStats>,
+    last_loaded: Res<LastLoaded>,
+    mut undo: ResMut<UndoLog>,
+    mut history: ResMut<History>,
+    interaction_query: Query<(&Interaction, &ResetButton), Changed<Interaction>>,
+    rule: Res<Rule>,
     config: Res<GameConfig>,
 ) {
-    for interaction in &mut interaction_query {
-        if *interaction == Interaction::Pressed {
-            for entity in query.iter() {
-                commands.entity(entity).despawn();
+    for (interaction, ResetButton(mode)) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        undo.finish_run(board.0.as_ref(), *rule, stats.generation);
+        let (before, generation) = (board.live_cells(), stats.generation);
+
+        board.clear();
+        history.clear();
+        *viewport = Viewport::default();
+        stats.generation = 0;
+        stats.last_step = None;
+
+        match mode {
+            ResetMode::Random => {
+                seed.0 = rand::thread_rng().gen();
+                fill_random_soup(board.0.as_mut(), &config, seed.0);
+            }
+            ResetMode::Clear => {}
+            ResetMode::Restore => {
+                for &(x, y) in &last_loaded.0 {
+                    board.set(x, y, true);
+                }
             }
-            spawn_grid(&mut commands, &config);
         }
+
+        undo.record_change(&before, board.0.as_ref(), (*rule, *rule), (generation, 0));
     }
-    // let mut rng = rand::thread_rng();
+}
+
+fn read_pattern(path: &Path) -> Result<PatternFile, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
 
-    // for mut cell in query.iter_mut() {
-    //     cell.is_alive = rng.gen_bool(config.initial_dencity);
-    // }
+    let file = match Format::detect(&text) {
+        Format::Macrocell => macrocell::parse(&text).map(PatternFile::Quadtree),
+        _ => pattern::parse(&text).map(PatternFile::Cells),
+    };
+    file.map_err(|e| format!("{}: {e}", path.display()))
 }
 
-fn update_grid_cell(
-    // mut commands: Commands,
-    mut query: Query<&mut GridCell>,
-    time: Res<Time>,
-    mut timer: ResMut<GridUpdateTimer>,
-    config: Res<GameConfig>,
+/// Queues pattern files dropped onto the window for loading.
+fn load_dropped_files(
+    mut events: EventReader<FileDragAndDrop>,
+    mut pending: ResMut<PendingPattern>,
 ) {
-    if timer.0.tick(time.delta()).just_finished() {
-        let mut next_generation_state: Vec<bool> = Vec::new();
+    for event in events.read() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = event {
+            match read_pattern(path_buf) {
+                Ok(file) => pending.0 = Some(file),
+                Err(message) => error!("could not load pattern: {message}"),
+            }
+        }
+    }
+}
 
-        for cell in query.iter() {
-            let mut number_of_neighbor_alive = 0;
+/// Replaces the board with the pending pattern and adopts the pattern's
+/// rule and topology if it names them. Cell patterns are centred on the
+/// visible area, which grows to fit them while keeping the window about the
+/// same size; Macrocell patterns become the board themselves, switching the
+/// simulation to HashLife. Loads that replace the board cannot be undone.
+#[allow(clippy::too_many_arguments)]
+fn place_pending_pattern(
+    mut pending: ResMut<PendingPattern>,
+    mut board: ResMut<Board>,
+    mut rule: ResMut<Rule>,
+    mut last_loaded: ResMut<LastLoaded>,
+    mut viewport: ResMut<Viewport>,
+    mut stats: ResMut<SimulationStats>,
+    mut undo: ResMut<UndoLog>,
+    mut history: ResMut<History>,
+    mut config: ResMut<GameConfig>,
+) {
+    let Some(file) = pending.0.take() else {
+        return;
+    };
 
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    if (dx, dy) == (0, 0) {
-                        continue;
-                    }
+    undo.finish_run(board.0.as_ref(), *rule, stats.generation);
+    history.clear();
+    let (rule_before, generation_before) = (*rule, stats.generation);
+    stats.generation = 0;
+    stats.last_step = None;
 
-                    let nx = (cell.x as isize + dx).rem_euclid(config.width as isize) as usize;
-                    let ny = (cell.y as isize + dy).rem_euclid(config.height as isize) as usize;
+    match file {
+        PatternFile::Cells(pattern) => {
+            let before = board.live_cells();
+            let (width, height) = (pattern.width as usize, pattern.height as usize);
+            let resized = width > config.width || height > config.height;
 
-                    if query.iter().nth(ny * config.width + nx).unwrap().is_alive {
-                        number_of_neighbor_alive += 1;
-                    }
+            if resized {
+                let bounded = matches!(config.backend, Backend::Dense | Backend::BitPacked);
+                if bounded && width.max(height) > MAX_BOARD_SIZE {
+                    config.backend = Backend::Sparse;
+                    info!("switched to the sparse backend for a {width}x{height} pattern");
                 }
+
+                let (window_width, window_height) = (config.window_width(), config.window_height());
+                config.width = config.width.max(width.min(MAX_BOARD_SIZE));
+                config.height = config.height.max(height.min(MAX_BOARD_SIZE));
+                config.cell_size = (window_width / config.width as f32)
+                    .min(window_height / config.height as f32)
+                    .max(1.0);
+
+                let topology = board.topology().unwrap_or(config.topology);
+                board.0 = config.backend.create(config.width, config.height, topology);
+                undo.clear();
+                info!("resized the board to {}x{}", config.width, config.height);
             }
 
-            match (cell.is_alive, number_of_neighbor_alive) {
-                (true, 2) | (true, 3) => next_generation_state.push(true),
-                (false, 3) => next_generation_state.push(true),
-                _ => next_generation_state.push(false),
+            board.clear();
+            let x = (config.width as i64 - pattern.width as i64) / 2;
+            let y = (config.height as i64 - pattern.height as i64) / 2;
+            pattern.place(board.0.as_mut(), x, y);
+
+            if let Some(pattern_rule) = pattern.rule {
+                *rule = pattern_rule;
             }
+            if let Some((topology, ..)) = pattern.bounded_grid {
+                board.set_topology(topology);
+            }
+
+            if !resized {
+                let generation = (generation_before, 0);
+                undo.record_change(&before, board.0.as_ref(), (rule_before, *rule), generation);
+            }
+
+            info!(
+                "loaded {} ({} cells)",
+                pattern.name.as_deref().unwrap_or("pattern"),
+                pattern.cells.len()
+            );
         }
+        PatternFile::Quadtree(macrocell) => {
+            if let Some(pattern_rule) = macrocell.rule {
+                *rule = pattern_rule;
+            }
 
-        for (i, mut cell) in query.iter_mut().enumerate() {
-            cell.is_alive = next_generation_state[i];
+            board.0 = Box::new(macrocell.universe);
+            undo.clear();
+            stats.generation = macrocell.generation;
+            config.backend = Backend::HashLife;
+            info!("loaded macrocell pattern ({} cells)", board.population());
         }
     }
+
+    last_loaded.0 = board.live_cells();
+    *viewport = Viewport::default();
 }
 
-fn update_grid_visuals(mut query: Query<(&GridCell, &mut Sprite), Changed<GridCell>>) {
-    for (cell, mut sprite) in query.iter_mut() {
-        sprite.color = if cell.is_alive {
-            Color::WHITE
-        } else {
-            Color::BLACK
+/// Writes the board to an `.rle` file in the working directory, or to an
+/// `.mc` file when it is a HashLife quadtree. Bounded boards are saved whole
+/// so that reloading puts every cell back in place.
+fn export_pattern(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    board: Res<Board>,
+    rule: Res<Rule>,
+    config: Res<GameConfig>,
+    stats: Res<SimulationStats>,
+) {
+    let ctrl = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keyboard.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let universe: &dyn Any = board.0.as_ref();
+    let (path, contents) = match universe.downcast_ref::<HashLife>() {
+        Some(hashlife) => (
+            format!("life-{seconds}.mc"),
+            macrocell::write(hashlife, &rule, stats.generation, &[]),
+        ),
+        None => (
+            format!("life-{seconds}.rle"),
+            rle::write(&board_pattern(&board, &rule, &config)),
+        ),
+    };
+
+    match std::fs::write(&path, contents) {
+        Ok(()) => info!("saved {path}"),
+        Err(e) => error!("could not save {path}: {e}"),
+    }
+}
+
+fn board_pattern(board: &Board, rule: &Rule, config: &GameConfig) -> Pattern {
+    let mut pattern = match board.topology() {
+        Some(topology) => Pattern {
+            bounded_grid: Some((topology, config.width, config.height)),
+            width: config.width as u64,
+            height: config.height as u64,
+            cells: board.live_cells(),
+            ..Pattern::default()
+        },
+        None => Pattern::from_cells(board.live_cells()),
+    };
+    pattern.rule = Some(*rule);
+    pattern
+}
+
+/// Saves the session to the snapshot file on Ctrl+S and restores it on
+/// Ctrl+L.
+#[allow(clippy::too_many_arguments)]
+fn save_or_load_session(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    board: Res<Board>,
+    rule: Res<Rule>,
+    seed: Res<Seed>,
+    stats: Res<SimulationStats>,
+    viewport: Res<Viewport>,
+    config: Res<GameConfig>,
+    mut pending: ResMut<PendingSnapshot>,
+) {
+    let ctrl = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl {
+        return;
+    }
+
+    let path = config.session_path();
+
+    if keyboard.just_pressed(KeyCode::KeyS) {
+        let snapshot = Snapshot::capture(
+            board.0.as_ref(),
+            *rule,
+            seed.0,
+            stats.generation,
+            (viewport.x, viewport.y),
+            &config,
+        );
+
+        match snapshot.write(path) {
+            Ok(()) => info!("saved session to {}", path.display()),
+            Err(message) => error!("could not save session: {message}"),
+        }
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyL) {
+        match Snapshot::read(path) {
+            Ok(session) => pending.0 = Some(session),
+            Err(message) => error!("could not load session: {message}"),
+        }
+    }
+}
+
+/// Replaces the whole session with the pending snapshot.
+#[allow(clippy::too_many_arguments)]
+fn restore_snapshot(
+    mut pending: ResMut<PendingSnapshot>,
+    mut board: ResMut<Board>,
+    mut rule: ResMut<Rule>,
+    mut seed: ResMut<Seed>,
+    mut stats: ResMut<SimulationStats>,
+    mut viewport: ResMut<Viewport>,
+    mut last_loaded: ResMut<LastLoaded>,
+    mut undo: ResMut<UndoLog>,
+    mut history: ResMut<History>,
+    mut timer: ResMut<GridUpdateTimer>,
+    mut jump: ResMut<Jump>,
+    mut playback: ResMut<Playback>,
+    mut config: ResMut<GameConfig>,
+) {
+    let Some((snapshot, universe)) = pending.0.take() else {
+        return;
+    };
+
+    undo.clear();
+    *config = GameConfig {
+        session: config.session.take(),
+        ..snapshot.config
+    };
+    *history = History::new(config.history_depth);
+    board.0 = universe;
+    *rule = snapshot.rule;
+    seed.0 = snapshot.seed;
+    stats.generation = snapshot.generation;
+    stats.last_step = None;
+    (viewport.x, viewport.y) = snapshot.viewport;
+    last_loaded.0 = board.live_cells();
+    // Other backends would run hyperspeed one generation at a time.
+    if config.backend != Backend::HashLife {
+        jump.hyperspeed = false;
+    }
+    playback.queued = 0;
+    timer
+        .0
+        .set_duration(Duration::from_millis(config.update_interval_millis));
+
+    info!("restored session at generation {}", stats.generation);
+}
+
+/// Spawns the board sprite, and respawns it in a resized window whenever
+/// the board size or cell size changes.
+fn resize_grid(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut images: ResMut<Assets<Image>>,
+    sprites: Query<(Entity, &Handle<Image>), With<BoardImage>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+    mut spawned: Local<Option<(usize, usize, f32)>>,
+) {
+    let size = (config.width, config.height, config.cell_size);
+    if !config.is_changed() || *spawned == Some(size) {
+        return;
+    }
+
+    for (entity, image) in &sprites {
+        images.remove(image);
+        commands.entity(entity).despawn();
+    }
+    spawn_board(&mut commands, &mut images, &config);
+
+    if let Ok(mut window) = windows.get_single_mut() {
+        window
+            .resolution
+            .set(config.window_width(), config.window_height());
+    }
+
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        transform.translation = Vec3::ZERO;
+        projection.scale = 1.0;
+    }
+
+    *spawned = Some(size);
+}
+
+/// Zooms the camera around the cursor with the mouse wheel, and pans it by
+/// dragging with the middle button or holding the arrow keys without Shift.
+fn control_camera(
+    mut wheel: EventReader<MouseWheel>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(
+        &Camera,
+        &GlobalTransform,
+        &mut Transform,
+        &mut OrthographicProjection,
+    )>,
+    mut last_cursor: Local<Option<Vec2>>,
+) {
+    let (Ok(window), Ok((camera, camera_transform, mut transform, mut projection))) =
+        (windows.get_single(), cameras.get_single_mut())
+    else {
+        return;
+    };
+
+    let cursor = window.cursor_position();
+
+    let notches: f32 = wheel
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 50.0,
+        })
+        .sum();
+
+    if notches != 0.0 {
+        let scale =
+            (projection.scale * ZOOM_STEP.powf(-notches)).clamp(MIN_CAMERA_SCALE, MAX_CAMERA_SCALE);
+
+        // Keep the point under the cursor where it is.
+        let anchor =
+            cursor.and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor));
+        if let Some(anchor) = anchor {
+            let centre = transform.translation.truncate();
+            let centre = anchor + (centre - anchor) * scale / projection.scale;
+            transform.translation = centre.extend(transform.translation.z);
+        }
+
+        projection.scale = scale;
+    }
+
+    if mouse.pressed(MouseButton::Middle) {
+        if let (Some(cursor), Some(last)) = (cursor, *last_cursor) {
+            // Screen y grows downwards, world y upwards.
+            let delta = (cursor - last) * projection.scale;
+            transform.translation.x -= delta.x;
+            transform.translation.y += delta.y;
+        }
+    }
+    *last_cursor = cursor;
+
+    // Shift and the arrow keys nudge the selection instead.
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let mut direction = Vec2::ZERO;
+    for (key, step) in [
+        (KeyCode::ArrowLeft, Vec2::NEG_X),
+        (KeyCode::ArrowRight, Vec2::X),
+        (KeyCode::ArrowUp, Vec2::Y),
+        (KeyCode::ArrowDown, Vec2::NEG_Y),
+    ] {
+        if keyboard.pressed(key) && !shift {
+            direction += step;
+        }
+    }
+
+    let pan = direction * PAN_SPEED * projection.scale * time.delta_seconds();
+    transform.translation += pan.extend(0.0);
+}
+
+/// Fits the live cells to the view on F and returns to the unzoomed 1:1
+/// scale on 1. Unbounded boards are re-centred on the pattern first, since
+/// only the visible area is drawn.
+fn fit_view(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    board: Res<Board>,
+    mut viewport: ResMut<Viewport>,
+    config: Res<GameConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    let (Ok(window), Ok((mut transform, mut projection))) =
+        (windows.get_single(), cameras.get_single_mut())
+    else {
+        return;
+    };
+
+    if keyboard.just_pressed(KeyCode::Digit1) {
+        projection.scale = 1.0;
+    }
+
+    if !keyboard.just_pressed(KeyCode::KeyF) {
+        return;
+    }
+
+    let Some(bounds) = board.bounds() else {
+        return;
+    };
+
+    let (width, height) = (config.width as i64, config.height as i64);
+    if board.topology().is_none() {
+        let (center_x, center_y) = bounds.center();
+        viewport.x = center_x - width / 2;
+        viewport.y = center_y - height / 2;
+    }
+
+    // The part of the pattern that is drawn, in board image coordinates.
+    let min_x = (bounds.min_x - viewport.x).clamp(0, width - 1) as f32;
+    let max_x = (bounds.max_x - viewport.x).clamp(0, width - 1) as f32 + 1.0;
+    let min_y = (bounds.min_y - viewport.y).clamp(0, height - 1) as f32;
+    let max_y = (bounds.max_y - viewport.y).clamp(0, height - 1) as f32 + 1.0;
+
+    let cell_size = config.cell_size;
+    transform.translation.x = (min_x + max_x) / 2.0 * cell_size - config.window_width() / 2.0;
+    transform.translation.y = config.window_height() / 2.0 - (min_y + max_y) / 2.0 * cell_size;
+
+    // Leave a margin of one cell around the pattern.
+    let fit_x = (max_x - min_x + 2.0) * cell_size / window.width();
+    let fit_y = (max_y - min_y + 2.0) * cell_size / window.height();
+    projection.scale = fit_x.max(fit_y).clamp(MIN_CAMERA_SCALE, MAX_CAMERA_SCALE);
+}
+
+/// Sets cells alive while the left mouse button is held and kills them
+/// while the right one is, recording each stroke as one undoable edit.
+/// Strokes that start on a button, select or place a paste are ignored.
+#[allow(clippy::too_many_arguments)]
+fn paint_cells(
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    interactions: Query<&Interaction>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut board: ResMut<Board>,
+    mut undo: ResMut<UndoLog>,
+    viewport: Res<Viewport>,
+    paste: Res<Paste>,
+    stats: Res<SimulationStats>,
+    rule: Res<Rule>,
+    config: Res<GameConfig>,
+    mut stroke: Local<Stroke>,
+) {
+    let is_alive = if mouse.pressed(MouseButton::Left) {
+        true
+    } else if mouse.pressed(MouseButton::Right) {
+        false
+    } else {
+        if stroke.active {
+            undo.commit(*rule, stats.generation);
+        }
+        *stroke = Stroke::default();
+        return;
+    };
+
+    if mouse.any_just_pressed([MouseButton::Left, MouseButton::Right]) {
+        undo.finish_run(board.0.as_ref(), *rule, stats.generation);
+        let on_ui = interactions.iter().any(|i| *i != Interaction::None);
+        let selecting = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        *stroke = Stroke {
+            active: !on_ui && !selecting && paste.pattern.is_none(),
+            last: None,
         };
     }
+
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+
+    if !stroke.active {
+        return;
+    }
+
+    let Some((x, y)) = cursor_cell(window, camera, camera_transform, &config) else {
+        stroke.last = None;
+        return;
+    };
+
+    let cell = (viewport.x + x as i64, viewport.y + y as i64);
+    if stroke.last == Some(cell) {
+        return;
+    }
+
+    for (x, y) in line_cells(stroke.last.unwrap_or(cell), cell) {
+        undo.set(board.0.as_mut(), x, y, is_alive);
+    }
+    stroke.last = Some(cell);
+}
+
+/// Handles the play/pause, step and speed controls from both the keyboard
+/// and the playback buttons.
+fn control_playback(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    buttons: Query<(&Interaction, &PlaybackButton), Changed<Interaction>>,
+    mut playback: ResMut<Playback>,
+    mut config: ResMut<GameConfig>,
+    mut timer: ResMut<GridUpdateTimer>,
+) {
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    let mut actions: Vec<_> = buttons
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, PlaybackButton(action))| *action)
+        .collect();
+
+    if keyboard.just_pressed(KeyCode::Space) {
+        actions.push(PlaybackAction::PlayPause);
+    }
+    if keyboard.just_pressed(KeyCode::KeyN) {
+        actions.push(if shift {
+            PlaybackAction::StepMany
+        } else {
+            PlaybackAction::Step
+        });
+    }
+    if keyboard.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        actions.push(PlaybackAction::Faster);
+    }
+    if keyboard.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        actions.push(PlaybackAction::Slower);
+    }
+
+    if keyboard.just_pressed(KeyCode::PageUp) {
+        playback.step_size = (playback.step_size * 10).min(MAX_STEP_SIZE);
+    }
+    if keyboard.just_pressed(KeyCode::PageDown) {
+        playback.step_size = (playback.step_size / 10).max(1);
+    }
+
+    for action in actions {
+        match action {
+            PlaybackAction::PlayPause => playback.paused = !playback.paused,
+            PlaybackAction::Step => playback.queued += 1,
+            PlaybackAction::StepMany => playback.queued += playback.step_size,
+            PlaybackAction::Faster => {
+                // Below a millisecond, run as fast as possible.
+                config.update_interval_millis /= 2;
+            }
+            PlaybackAction::Slower => {
+                config.update_interval_millis =
+                    (config.update_interval_millis * 2).clamp(1, MAX_INTERVAL_MILLIS);
+            }
+        }
+
+        timer
+            .0
+            .set_duration(Duration::from_millis(config.update_interval_millis));
+    }
+}
+
+/// Advances the board by any queued steps and then, unless paused, by as
+/// many generations as the speed calls for, remembering them in the history
+/// outside hyperspeed. An interval of zero runs as fast as `STEP_BUDGET` allows,
+/// independently of the frame rate.
+#[allow(clippy::too_many_arguments)]
+fn update_grid_cell(
+    time: Res<Time>,
+    mut timer: ResMut<GridUpdateTimer>,
+    mut board: ResMut<Board>,
+    mut jump: ResMut<Jump>,
+    mut stats: ResMut<SimulationStats>,
+    mut playback: ResMut<Playback>,
+    mut undo: ResMut<UndoLog>,
+    mut history: ResMut<History>,
+    rule: Res<Rule>,
+    config: Res<GameConfig>,
+) {
+    let started = Instant::now();
+    let mut advance = || {
+        undo.start_run(board.0.as_ref(), stats.generation);
+
+        // Hyperspeed leaps over generations too fast to be worth recording.
+        if jump.hyperspeed {
+            history.forget_from(stats.generation);
+            board.step_pow2(&rule, jump.exponent);
+            stats.generation = stats.generation.saturating_add(1 << jump.exponent);
+            stats.last_step = None;
+            jump.exponent = (jump.exponent + 1).min(MAX_JUMP_EXPONENT);
+        } else {
+            history.record_step(stats.generation, board.0.as_ref());
+            stats.last_step = Some(board.step_counting(&rule));
+            stats.generation += 1;
+        }
+    };
+
+    while playback.queued > 0 {
+        advance();
+        playback.queued -= 1;
+        if started.elapsed() >= STEP_BUDGET {
+            return;
+        }
+    }
+
+    if playback.paused {
+        return;
+    }
+
+    let due = match config.update_interval_millis {
+        0 => u32::MAX,
+        _ => timer.0.tick(time.delta()).times_finished_this_tick(),
+    };
+
+    for _ in 0..due {
+        advance();
+        if started.elapsed() >= STEP_BUDGET {
+            break;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn control_jumps(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut board: ResMut<Board>,
+    mut jump: ResMut<Jump>,
+    mut stats: ResMut<SimulationStats>,
+    mut undo: ResMut<UndoLog>,
+    mut history: ResMut<History>,
+    mut playback: ResMut<Playback>,
+    rule: Res<Rule>,
+    config: Res<GameConfig>,
+) {
+    if keyboard.just_pressed(KeyCode::BracketRight) {
+        jump.exponent = (jump.exponent + 1).min(MAX_JUMP_EXPONENT);
+        info!("jump size: 2^{}", jump.exponent);
+    }
+
+    if keyboard.just_pressed(KeyCode::BracketLeft) {
+        jump.exponent = jump.exponent.saturating_sub(1);
+        info!("jump size: 2^{}", jump.exponent);
+    }
+
+    // Only HashLife can skip ahead; the other backends queue the jump as
+    // single steps, which `update_grid_cell` spreads over frames.
+    if keyboard.just_pressed(KeyCode::KeyJ) && config.backend != Backend::HashLife {
+        let steps = (1u64 << jump.exponent).min(MAX_STEP_SIZE);
+        playback.queued = playback.queued.saturating_add(steps);
+        info!("queued {steps} generations");
+    } else if keyboard.just_pressed(KeyCode::KeyJ) {
+        undo.start_run(board.0.as_ref(), stats.generation);
+        history.forget_from(stats.generation);
+        board.step_pow2(&rule, jump.exponent);
+        stats.generation = stats.generation.saturating_add(1 << jump.exponent);
+        stats.last_step = None;
+        info!("jumped 2^{} generations", jump.exponent);
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyH) {
+        if config.backend == Backend::HashLife {
+            jump.hyperspeed = !jump.hyperspeed;
+            info!("hyperspeed: {}", jump.hyperspeed);
+        } else {
+            warn!("hyperspeed needs the HashLife backend");
+        }
+    }
+}
+
+fn cycle_rule(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut rule: ResMut<Rule>,
+    mut undo: ResMut<UndoLog>,
+    board: Res<Board>,
+    stats: Res<SimulationStats>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyR) {
+        undo.finish_run(board.0.as_ref(), *rule, stats.generation);
+        let before = *rule;
+        *rule = rule.next_preset();
+        undo.record(Edit {
+            flips: Vec::new(),
+            rule: (before, *rule),
+            generation: (stats.generation, stats.generation),
+        });
+        info!("rule: {} ({})", *rule, rule.name().unwrap_or("custom"));
+    }
+}
+
+/// Undoes the latest edit on Ctrl+Z and redoes it on Ctrl+Shift+Z, pausing
+/// the simulation so that the result stays on screen.
+fn undo_redo(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut board: ResMut<Board>,
+    mut rule: ResMut<Rule>,
+    mut stats: ResMut<SimulationStats>,
+    mut undo: ResMut<UndoLog>,
+    mut playback: ResMut<Playback>,
+) {
+    let ctrl = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keyboard.just_pressed(KeyCode::KeyZ) {
+        return;
+    }
+
+    undo.finish_run(board.0.as_ref(), *rule, stats.generation);
+
+    let restored = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        undo.redo(board.0.as_mut())
+            .map(|edit| (edit.rule.1, edit.generation.1))
+    } else {
+        undo.undo(board.0.as_mut())
+            .map(|edit| (edit.rule.0, edit.generation.0))
+    };
+
+    if let Some((edit_rule, generation)) = restored {
+        *rule = edit_rule;
+        stats.generation = generation;
+        stats.last_step = None;
+        playback.paused = true;
+    }
+}
+
+/// Steps back through the history on B and forward on Shift+B, and rewinds
+/// to the generation under the cursor while the scrubber is held. Going
+/// back from the latest generation first records it, so that it can be
+/// returned to.
+fn travel_history(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    scrubbers: Query<(&Interaction, &RelativeCursorPosition), With<Scrubber>>,
+    mut history: ResMut<History>,
+    mut board: ResMut<Board>,
+    mut stats: ResMut<SimulationStats>,
+    mut undo: ResMut<UndoLog>,
+    mut playback: ResMut<Playback>,
+) {
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let back = keyboard.just_pressed(KeyCode::KeyB) && !shift;
+    let forward = keyboard.just_pressed(KeyCode::KeyB) && shift;
+    let scrubbed = scrubbers
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .find_map(|(_, cursor)| cursor.normalized)
+        .map(|cursor| cursor.x.clamp(0.0, 1.0));
+
+    if !back && !forward && scrubbed.is_none() {
+        return;
+    }
+
+    if !forward && history.position(stats.generation).is_none() {
+        history.record(stats.generation, board.0.as_ref());
+    }
+
+    let target = match scrubbed {
+        Some(fraction) => {
+            Some((fraction * history.len().saturating_sub(1) as f32).round() as usize)
+        }
+        None if back => history.before(stats.generation),
+        None => history.after(stats.generation),
+    };
+    let Some(index) = target.filter(|&index| history.position(stats.generation) != Some(index))
+    else {
+        return;
+    };
+
+    undo.start_run(board.0.as_ref(), stats.generation);
+    if let Some(generation) = history.restore(index, board.0.as_mut()) {
+        stats.generation = generation;
+        stats.last_step = None;
+        playback.paused = true;
+        playback.queued = 0;
+    }
+}
+
+fn cycle_topology(keyboard: Res<ButtonInput<KeyCode>>, mut board: ResMut<Board>) {
+    if keyboard.just_pressed(KeyCode::KeyT) {
+        if let Some(topology) = board.topology() {
+            board.set_topology(topology.next());
+            info!("topology: {}", topology.next());
+        }
+    }
+}
+
+/// Keeps the live cells of an unbounded universe on screen by re-centring
+/// the viewport whenever they drift out of it.
+fn follow_active_region(
+    board: Res<Board>,
+    mut viewport: ResMut<Viewport>,
+    config: Res<GameConfig>,
+) {
+    if !board.is_changed() || board.topology().is_some() {
+        return;
+    }
+
+    let Some(active) = board.bounds() else {
+        return;
+    };
+
+    let (width, height) = (config.width as i64, config.height as i64);
+    let visible = Bounds {
+        min_x: viewport.x,
+        min_y: viewport.y,
+        max_x: viewport.x + width - 1,
+        max_y: viewport.y + height - 1,
+    };
+
+    if !visible.contains(active.min_x, active.min_y)
+        || !visible.contains(active.max_x, active.max_y)
+    {
+        let (center_x, center_y) = active.center();
+        viewport.x = center_x - width / 2;
+        viewport.y = center_y - height / 2;
+    }
+}
+
+/// Redraws the board image whenever the board or the viewport changes.
+fn draw_board(
+    board: Res<Board>,
+    viewport: Res<Viewport>,
+    sprites: Query<Ref<Handle<Image>>, With<BoardImage>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Ok(handle) = sprites.get_single() else {
+        return;
+    };
+    if !board.is_changed() && !viewport.is_changed() && !handle.is_added() {
+        return;
+    }
+    let Some(image) = images.get_mut(handle.id()) else {
+        return;
+    };
+
+    // Go by the image rather than the config, which may already have been
+    // resized for a sprite that is not spawned yet.
+    let width = image.width() as usize;
+    let visible = Bounds {
+        min_x: viewport.x,
+        min_y: viewport.y,
+        max_x: viewport.x + width as i64 - 1,
+        max_y: viewport.y + image.height() as i64 - 1,
+    };
+
+    for texel in image.data.chunks_exact_mut(4) {
+        texel.copy_from_slice(&DEAD_TEXEL);
+    }
+    for (x, y) in selection::live_cells_in(board.0.as_ref(), &visible) {
+        let i = ((y - viewport.y) as usize * width + (x - viewport.x) as usize) * 4;
+        image.data[i..i + 4].copy_from_slice(&ALIVE_TEXEL);
+    }
 }
 
 fn setup(mut commands: Commands, config: Res<GameConfig>) {
     commands.spawn(Camera2dBundle::default());
     info!("{:?}", config);
 
-    spawn_grid(&mut commands, &config);
+    let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut universe = config
+        .backend
+        .create(config.width, config.height, config.topology);
+    fill_random_soup(universe.as_mut(), &config, seed);
+
+    commands.insert_resource(LastLoaded(universe.live_cells()));
+    commands.insert_resource(Board(universe));
+    commands.insert_resource(Seed(seed));
+    commands.insert_resource(SimulationStats::default());
+    commands.insert_resource(Viewport::default());
+}
+
+fn update_seed_text(seed: Res<Seed>, mut query: Query<&mut Text, With<SeedText>>) {
+    if !seed.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!("seed: {}", seed.0);
+    }
+}
+
+/// Keeps the population current and measures generations per second over
+/// half-second windows.
+fn update_stats(
+    time: Res<Time>,
+    board: Res<Board>,
+    mut stats: ResMut<SimulationStats>,
+    mut window: Local<Option<(f64, u64)>>,
+) {
+    if board.is_changed() {
+        stats.population = board.population();
+    }
+
+    let now = time.elapsed_seconds_f64();
+    let (start, start_generation) = *window.get_or_insert((now, stats.generation));
+
+    if now - start >= 0.5 {
+        let generations = stats.generation.saturating_sub(start_generation);
+        stats.generations_per_second = generations as f64 / (now - start);
+        *window = Some((now, stats.generation));
+    }
+}
+
+fn update_hud(
+    stats: Res<SimulationStats>,
+    rule: Res<Rule>,
+    history: Res<History>,
+    selection: Res<Selection>,
+    paste: Res<Paste>,
+    mut query: Query<&mut Text, With<HudText>>,
+) {
+    let changed = stats.is_changed() || rule.is_changed() || history.is_changed();
+    if !changed && !selection.is_changed() && !paste.is_changed() {
+        return;
+    }
+
+    let changes = match stats.last_step {
+        Some(changes) => format!("+{} / -{}", changes.births, changes.deaths),
+        None => String::from("-"),
+    };
+    let rule_name = rule
+        .name()
+        .map(|name| format!(" ({name})"))
+        .unwrap_or_default();
+
+    let mut editing = String::new();
+    if let Some(area) = selection.0 {
+        editing += &format!("\nselection: {}x{}", area.width(), area.height());
+    }
+    if let Some(pattern) = &paste.pattern {
+        let action = if paste.repeat { "stamping" } else { "pasting" };
+        editing += &format!(
+            "\n{action}: {}x{} ({})",
+            pattern.width, pattern.height, paste.mode
+        );
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "generation: {}\npopulation: {}\nbirths / deaths: {changes}\nrule: {}{rule_name}\n{:.1} gen/s\nhistory: {} / {}{editing}",
+            stats.generation,
+            stats.population,
+            *rule,
+            stats.generations_per_second,
+            history.len(),
+            history.depth(),
+        );
+    }
 }
 
-fn setup_ui(mut commands: Commands) {
+fn update_scrubber(
+    history: Res<History>,
+    stats: Res<SimulationStats>,
+    mut handles: Query<&mut Style, With<ScrubberHandle>>,
+) {
+    if !history.is_changed() && !stats.is_changed() {
+        return;
+    }
+
+    let fraction = match history.position(stats.generation) {
+        Some(index) if history.len() > 1 => index as f32 / (history.len() - 1) as f32,
+        _ => 1.0,
+    };
+
+    for mut style in handles.iter_mut() {
+        style.left = Val::Percent(fraction * 100.0);
+    }
+}
+
+fn update_playback_text(
+    playback: Res<Playback>,
+    config: Res<GameConfig>,
+    mut speed_texts: Query<&mut Text, With<SpeedText>>,
+    mut labels: Query<(&mut Text, &PlaybackLabel), Without<SpeedText>>,
+) {
+    if !playback.is_changed() && !config.is_changed() {
+        return;
+    }
+
+    let speed = match config.update_interval_millis {
+        0 => String::from("max"),
+        millis => {
+            let rate = format!("{:.2}", 1000.0 / millis as f64);
+            format!("{} gen/s", rate.trim_end_matches('0').trim_end_matches('.'))
+        }
+    };
+    let paused = if playback.paused { " (paused)" } else { "" };
+
+    for mut text in speed_texts.iter_mut() {
+        text.sections[0].value = format!("speed: {speed}{paused}");
+    }
+
+    for (mut text, PlaybackLabel(action)) in labels.iter_mut() {
+        text.sections[0].value = action.label(&playback);
+    }
+}
+
+fn spawn_button(parent: &mut ChildBuilder, label: &str, button: impl Bundle, text: impl Bundle) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(90.0),
+                    height: Val::Px(36.0),
+                    margin: UiRect::all(Val::Px(4.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::srgb(0.15, 0.15, 0.15).into(),
+                ..Default::default()
+            },
+            button,
+        ))
+        .with_children(|parent| {
+            let style = TextStyle {
+                font_size: 18.0,
+                ..default()
+            };
+            parent.spawn((TextBundle::from_section(label, style), text));
+        });
+}
+
+fn setup_ui(mut commands: Commands, playback: Res<Playback>) {
     commands
         .spawn(NodeBundle {
             style: Style {
                 width: Val::Percent(100.0),
                 height: Val::Percent(100.0),
+                flex_wrap: FlexWrap::Wrap,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::FlexEnd,
+                align_content: AlignContent::FlexEnd,
                 ..Default::default()
             },
             ..Default::default()
@@ -165,32 +1407,125 @@ fn setup_ui(mut commands: Commands) {
                 .spawn((
                     ButtonBundle {
                         style: Style {
-                            width: Val::Px(150.0),
-                            height: Val::Px(60.0),
-                            margin: UiRect::all(Val::Px(20.0)),
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
+                            width: Val::Percent(100.0),
+                            height: Val::Px(12.0),
+                            margin: UiRect::vertical(Val::Px(4.0)),
                             ..default()
                         },
                         background_color: Color::srgb(0.15, 0.15, 0.15).into(),
-                        ..Default::default()
+                        ..default()
                     },
-                    ResetButton,
+                    RelativeCursorPosition::default(),
+                    Scrubber,
                 ))
                 .with_children(|parent| {
-                    parent.spawn(TextBundle::from("Reset"));
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Percent(100.0),
+                                width: Val::Px(6.0),
+                                height: Val::Percent(100.0),
+                                margin: UiRect::left(Val::Px(-3.0)),
+                                ..default()
+                            },
+                            background_color: Color::srgb(0.8, 0.8, 0.8).into(),
+                            ..default()
+                        },
+                        ScrubberHandle,
+                    ));
                 });
+
+            for mode in [ResetMode::Random, ResetMode::Clear, ResetMode::Restore] {
+                spawn_button(parent, mode.label(), ResetButton(mode), ());
+            }
+
+            for action in [
+                PlaybackAction::PlayPause,
+                PlaybackAction::Step,
+                PlaybackAction::StepMany,
+                PlaybackAction::Slower,
+                PlaybackAction::Faster,
+            ] {
+                let label = action.label(&playback);
+                spawn_button(
+                    parent,
+                    &label,
+                    PlaybackButton(action),
+                    PlaybackLabel(action),
+                );
+            }
         });
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(35.0),
+            left: Val::Px(5.0),
+            padding: UiRect::all(Val::Px(4.0)),
+            ..default()
+        })
+        .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        HudText,
+    ));
+
+    commands.spawn((
+        TextBundle::from("speed: -").with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            right: Val::Px(5.0),
+            ..default()
+        }),
+        SpeedText,
+    ));
+
+    commands.spawn((
+        TextBundle::from("seed: -").with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            left: Val::Px(5.0),
+            ..default()
+        }),
+        SeedText,
+    ));
 }
 
 fn main() {
-    let game_config = GameConfig {
-        width: 50,
-        height: 50,
-        cell_size: 10.0,
-        initial_dencity: 0.3,
-        update_interval_millis: 100,
-    };
+    let mut game_config = GameConfig::default();
+
+    if let Err(message) = game_config.apply_args(std::env::args().skip(1)) {
+        eprintln!("{message}\n{USAGE}");
+        std::process::exit(2);
+    }
+
+    let pattern = game_config.pattern.as_deref().map(read_pattern).transpose();
+    let pattern = pattern.unwrap_or_else(|message| {
+        eprintln!("{message}");
+        std::process::exit(1);
+    });
+
+    let session = game_config
+        .session
+        .as_deref()
+        .map(Snapshot::read)
+        .transpose();
+    let session = session.unwrap_or_else(|message| {
+        eprintln!("{message}");
+        std::process::exit(1);
+    });
+    if let Some((snapshot, _)) = &session {
+        game_config = GameConfig {
+            session: game_config.session.take(),
+            ..snapshot.config.clone()
+        };
+    }
 
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
@@ -201,14 +1536,64 @@ fn main() {
             }),
             ..Default::default()
         }))
+        .insert_resource(game_config.rule)
+        .insert_resource(PendingPattern(pattern))
+        .insert_resource(PendingSnapshot(session))
+        .init_resource::<Jump>()
+        .init_resource::<Playback>()
+        .init_resource::<UndoLog>()
+        .insert_resource(History::new(game_config.history_depth))
+        .insert_resource(GridUpdateTimer(Timer::new(
+            Duration::from_millis(game_config.update_interval_millis),
+            TimerMode::Repeating,
+        )))
         .insert_resource(game_config)
-        .insert_resource(GridUpdateTimer {
-            0: Timer::new(
-                Duration::from_millis(game_config.update_interval_millis),
-                TimerMode::Repeating,
-            ),
-        })
+        .add_plugins((LibraryPanelPlugin, SelectionToolsPlugin))
+        .configure_sets(
+            Update,
+            (GameSet::Controls, GameSet::Edit, GameSet::Advance).chain(),
+        )
         .add_systems(Startup, (setup, setup_ui))
-        .add_systems(Update, (update_grid_cell, update_grid_visuals, reset_game))
+        .add_systems(
+            Update,
+            (
+                (
+                    cycle_rule,
+                    cycle_topology,
+                    undo_redo,
+                    travel_history,
+                    control_jumps,
+                    control_playback,
+                )
+                    .chain()
+                    .in_set(GameSet::Controls),
+                (
+                    load_dropped_files,
+                    place_pending_pattern,
+                    save_or_load_session,
+                    restore_snapshot,
+                    resize_grid,
+                    export_pattern,
+                    control_camera,
+                    fit_view,
+                    paint_cells,
+                )
+                    .chain()
+                    .in_set(GameSet::Edit),
+                (
+                    update_grid_cell,
+                    follow_active_region,
+                    draw_board,
+                    reset_game,
+                    update_stats,
+                    update_seed_text,
+                    update_playback_text,
+                    update_scrubber,
+                    update_hud,
+                )
+                    .chain()
+                    .in_set(GameSet::Advance),
+            ),
+        )
         .run();
 }
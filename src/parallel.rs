@@ -0,0 +1,137 @@
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+
+/// Boards with fewer elements than this are filled on the calling thread,
+/// where spawning tasks would cost more than it saves.
+const PARALLEL_THRESHOLD: usize = 4096;
+
+/// Bevy's compute pool, which boards are stepped on.
+///
+/// `Grid::step` and `BitGrid::step` hand it to their `step_in`, which
+/// computes the next generation with `for_each_row`, so large boards are
+/// stepped in parallel bands of rows. Tests pass `step_in` other pools.
+pub(crate) fn compute_pool() -> &'static TaskPool {
+    ComputeTaskPool::get_or_init(TaskPool::default)
+}
+
+/// Calls `fill_row(y, row)` for every `row_len`-long row of `output`,
+/// splitting the rows into one band per thread of `pool`.
+///
+/// Each row is written by exactly one call, so as long as `fill_row` only
+/// reads shared state the result is identical to filling row by row.
+pub(crate) fn for_each_row<T: Send>(
+    pool: &TaskPool,
+    output: &mut [T],
+    row_len: usize,
+    fill_row: impl Fn(usize, &mut [T]) + Sync,
+) {
+    if row_len == 0 {
+        return;
+    }
+
+    let rows = output.len() / row_len;
+    let threads = pool.thread_num();
+
+    if output.len() < PARALLEL_THRESHOLD || threads < 2 || rows < 2 {
+        for (y, row) in output.chunks_mut(row_len).enumerate() {
+            fill_row(y, row);
+        }
+        return;
+    }
+
+    let band_rows = rows.div_ceil(threads);
+    let fill_row = &fill_row;
+
+    pool.scope(|scope| {
+        for (band, chunk) in output.chunks_mut(band_rows * row_len).enumerate() {
+            scope.spawn(async move {
+                for (i, row) in chunk.chunks_mut(row_len).enumerate() {
+                    fill_row(band * band_rows + i, row);
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::tasks::TaskPoolBuilder;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        bitgrid::BitGrid,
+        grid::Grid,
+        rule::Rule,
+        topology::Topology,
+        universe::{sorted_cells, Universe},
+    };
+
+    fn randomise(universe: &mut dyn Universe, width: usize, height: usize) {
+        let mut rng = StdRng::seed_from_u64(11);
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                universe.set(x, y, rng.gen_bool(0.4));
+            }
+        }
+    }
+
+    #[test]
+    fn banded_rows_match_sequential_rows() {
+        let banded = TaskPoolBuilder::new().num_threads(4).build();
+        let sequential = TaskPoolBuilder::new().num_threads(1).build();
+        assert!(banded.thread_num() >= 2);
+
+        let fill = |y: usize, row: &mut [usize]| {
+            for (x, cell) in row.iter_mut().enumerate() {
+                *cell = y * 1000 + x;
+            }
+        };
+        let mut a = vec![0; 97 * 101];
+        let mut b = vec![0; 97 * 101];
+        for_each_row(&banded, &mut a, 97, fill);
+        for_each_row(&sequential, &mut b, 97, fill);
+        assert!(a.len() >= PARALLEL_THRESHOLD);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn grid_steps_the_same_on_any_pool() {
+        let banded = TaskPoolBuilder::new().num_threads(4).build();
+        let sequential = TaskPoolBuilder::new().num_threads(1).build();
+        let (width, height) = (131, 97);
+        assert!(width * height >= PARALLEL_THRESHOLD);
+
+        for topology in [Topology::Plane, Topology::Torus] {
+            let mut a = Grid::new(width, height, topology);
+            randomise(&mut a, width, height);
+            let mut b = a.clone();
+
+            for _ in 0..10 {
+                a.step_in(&Rule::LIFE, &banded);
+                b.step_in(&Rule::LIFE, &sequential);
+                assert_eq!(sorted_cells(&a), sorted_cells(&b));
+            }
+        }
+    }
+
+    #[test]
+    fn bitgrid_steps_the_same_on_any_pool() {
+        let banded = TaskPoolBuilder::new().num_threads(4).build();
+        let sequential = TaskPoolBuilder::new().num_threads(1).build();
+        // 3 words per row, so the board holds more words than the threshold.
+        let (width, height): (usize, usize) = (130, 1400);
+        assert!(width.div_ceil(64) * height >= PARALLEL_THRESHOLD);
+
+        for topology in [Topology::Plane, Topology::Torus] {
+            let mut a = BitGrid::new(width, height, topology);
+            randomise(&mut a, width, height);
+            let mut b = a.clone();
+
+            for _ in 0..10 {
+                a.step_in(&Rule::LIFE, &banded);
+                b.step_in(&Rule::LIFE, &sequential);
+                assert_eq!(sorted_cells(&a), sorted_cells(&b));
+            }
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::{error::Error, fmt};
+
+use crate::{
+    hashlife::{macrocell, HashLife},
+    rule::Rule,
+    topology::Topology,
+    universe::Universe,
+};
+
+pub mod life105;
+pub mod life106;
+pub mod plaintext;
+pub mod rle;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+    Macrocell,
+}
+
+impl Format {
+    /// Guesses the format of a pattern file from its contents.
+    pub fn detect(text: &str) -> Format {
+        let text = text.trim_start();
+
+        if text.starts_with(life106::HEADER) {
+            return Format::Life106;
+        }
+        if text.starts_with(life105::HEADER) {
+            return Format::Life105;
+        }
+        if text.starts_with(macrocell::HEADER) {
+            return Format::Macrocell;
+        }
+
+        // RLE files may open with `#` comments; their first other line is
+        // the `x = ...` header.
+        let first_line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'));
+
+        match first_line {
+            Some(line) if line.starts_with('x') && line.contains('=') => Format::Rle,
+            _ => Format::Plaintext,
+        }
+    }
+}
+
+/// Parses a pattern in any supported format, detected from its contents.
+///
+/// Macrocell files are flattened into a list of cells here; use
+/// `macrocell::parse` to keep them as a quadtree.
+pub fn parse(text: &str) -> Result<Pattern, ParsePatternError> {
+    match Format::detect(text) {
+        Format::Rle => rle::parse(text),
+        Format::Plaintext => plaintext::parse(text),
+        Format::Life105 => life105::parse(text),
+        Format::Life106 => life106::parse(text),
+        Format::Macrocell => macrocell::parse(text).map(Pattern::from),
+    }
+}
+
+/// Flattens a quadtree into a list of cells, which takes time and memory in
+/// proportion to its population.
+impl From<macrocell::Macrocell> for Pattern {
+    fn from(macrocell: macrocell::Macrocell) -> Self {
+        Pattern {
+            rule: macrocell.rule,
+            comments: macrocell.comments,
+            ..Pattern::from_cells(macrocell.universe.live_cells())
+        }
+    }
+}
+
+pub fn write(pattern: &Pattern, format: Format) -> String {
+    match format {
+        Format::Rle => rle::write(pattern),
+        Format::Plaintext => plaintext::write(pattern),
+        Format::Life105 => life105::write(pattern),
+        Format::Life106 => life106::write(pattern),
+        Format::Macrocell => {
+            let mut universe = HashLife::new();
+            let (x, y) = (pattern.width as i64 / 2, pattern.height as i64 / 2);
+            pattern.place(&mut universe, -x, -y);
+            macrocell::write(
+                &universe,
+                &pattern.rule.unwrap_or_default(),
+                0,
+                &pattern.comments,
+            )
+        }
+    }
+}
+
+/// A finite arrangement of live cells, as read from or written to a
+/// pattern file.
+///
+/// Cells are relative to the top-left corner of the pattern, with `y`
+/// growing downwards as in every Life file format.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pattern {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+    pub rule: Option<Rule>,
+    /// Golly bounded grid the pattern was saved on, if any.
+    pub bounded_grid: Option<(Topology, usize, usize)>,
+    pub width: u64,
+    pub height: u64,
+    pub cells: Vec<(i64, i64)>,
+}
+
+impl Pattern {
+    /// Builds a pattern whose top-left corner is the top-left of the
+    /// bounding box of `cells`.
+    pub fn from_cells(cells: impl IntoIterator<Item = (i64, i64)>) -> Pattern {
+        let mut cells: Vec<_> = cells.into_iter().collect();
+        let min_x = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let min_y = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+
+        for (x, y) in &mut cells {
+            *x -= min_x;
+            *y -= min_y;
+        }
+        cells.sort_by_key(|&(x, y)| (y, x));
+
+        Pattern {
+            width: cells.iter().map(|&(x, _)| x as u64 + 1).max().unwrap_or(0),
+            height: cells.iter().map(|&(_, y)| y as u64 + 1).max().unwrap_or(0),
+            cells,
+            ..Pattern::default()
+        }
+    }
+
+    /// The pattern turned or mirrored by `symmetry`, with its top-left
+    /// corner still at the origin.
+    pub fn transformed(&self, symmetry: Symmetry) -> Pattern {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let mut cells: Vec<_> = self
+            .cells
+            .iter()
+            .map(|&(x, y)| match symmetry {
+                Symmetry::RotateClockwise => (height - 1 - y, x),
+                Symmetry::RotateCounterclockwise => (y, width - 1 - x),
+                Symmetry::FlipHorizontal => (width - 1 - x, y),
+                Symmetry::FlipVertical => (x, height - 1 - y),
+            })
+            .collect();
+        cells.sort_by_key(|&(x, y)| (y, x));
+
+        let (width, height) = match symmetry {
+            Symmetry::RotateClockwise | Symmetry::RotateCounterclockwise => {
+                (self.height, self.width)
+            }
+            Symmetry::FlipHorizontal | Symmetry::FlipVertical => (self.width, self.height),
+        };
+
+        Pattern {
+            width,
+            height,
+            cells,
+            ..self.clone()
+        }
+    }
+
+    /// Sets the pattern's cells alive with its top-left corner at `(x, y)`.
+    pub fn place(&self, universe: &mut dyn Universe, x: i64, y: i64) {
+        for &(dx, dy) in &self.cells {
+            universe.set(x + dx, y + dy, true);
+        }
+    }
+}
+
+/// A quarter turn or a mirror image, as seen on screen with `y` growing
+/// downwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    RotateClockwise,
+    RotateCounterclockwise,
+    /// Mirrors left and right.
+    FlipHorizontal,
+    /// Mirrors top and bottom.
+    FlipVertical,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsePatternError {
+    /// 1-based line number, or 0 if the problem is not tied to a line.
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParsePatternError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        ParsePatternError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl Error for ParsePatternError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_every_format() {
+        let glider = Pattern {
+            name: Some("Glider".to_string()),
+            ..Pattern::from_cells([(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)])
+        };
+
+        for format in [
+            Format::Rle,
+            Format::Plaintext,
+            Format::Life105,
+            Format::Life106,
+            Format::Macrocell,
+        ] {
+            let text = write(&glider, format);
+            assert_eq!(Format::detect(&text), format, "{text}");
+            assert_eq!(parse(&text).unwrap().cells, glider.cells, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn detects_rle_after_comments() {
+        assert_eq!(
+            Format::detect("#C a comment\n\nx = 1, y = 1\no!"),
+            Format::Rle
+        );
+        assert_eq!(Format::detect("!x = 1\n.O"), Format::Plaintext);
+    }
+}
@@ -0,0 +1,133 @@
+//! Life 1.05 patterns: `#P x y` blocks of `.`/`*` rows, with the rule given
+//! as `#N` (Conway's Life) or `#R` in S/B notation.
+
+use super::{ParsePatternError, Pattern};
+use crate::rule::Rule;
+
+pub const HEADER: &str = "#Life 1.05";
+
+pub fn parse(text: &str) -> Result<Pattern, ParsePatternError> {
+    let mut cells = Vec::new();
+    let mut comments = Vec::new();
+    let mut rule = None;
+    let (mut origin_x, mut origin_y, mut row) = (0, 0, 0);
+
+    for (number, line) in text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+    {
+        let error = |message: String| ParsePatternError::new(number, message);
+
+        if line.is_empty() || line.starts_with(HEADER) {
+            continue;
+        }
+
+        if let Some(directive) = line.strip_prefix('#') {
+            let mut chars = directive.chars();
+            let kind = chars.next();
+            let argument = chars.as_str().trim();
+
+            match kind {
+                Some('D') => comments.push(argument.to_string()),
+                Some('N') => rule = Some(Rule::LIFE),
+                Some('R') => {
+                    rule = Some(
+                        argument
+                            .parse()
+                            .map_err(|e| error(format!("invalid rule: {e}")))?,
+                    );
+                }
+                Some('P') => {
+                    let mut coordinates = argument.split_whitespace().map(str::parse::<i64>);
+                    match (coordinates.next(), coordinates.next()) {
+                        (Some(Ok(x)), Some(Ok(y))) => (origin_x, origin_y, row) = (x, y, 0),
+                        _ => return Err(error(format!("expected \"#P x y\", found \"{line}\""))),
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '*' => cells.push((origin_x + x as i64, origin_y + row)),
+                '.' => {}
+                c => return Err(error(format!("unexpected '{c}' in pattern row"))),
+            }
+        }
+        row += 1;
+    }
+
+    Ok(Pattern {
+        comments,
+        rule,
+        ..Pattern::from_cells(cells)
+    })
+}
+
+/// Writes the pattern as a single `#P` block whose origin is the pattern's
+/// centre, as Life 1.05 readers expect.
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = format!("{HEADER}\n");
+
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("#D {name}\n"));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#D {comment}\n"));
+    }
+
+    match pattern.rule {
+        Some(rule) if rule != Rule::LIFE => out.push_str(&format!("#R {}\n", rule.sb_notation())),
+        _ => out.push_str("#N\n"),
+    }
+
+    let (width, height) = (pattern.width as usize, pattern.height as usize);
+    out.push_str(&format!(
+        "#P {} {}\n",
+        -(width as i64 / 2),
+        -(height as i64 / 2)
+    ));
+
+    let mut rows = vec![vec!['.'; width]; height];
+    for &(x, y) in &pattern.cells {
+        rows[y as usize][x as usize] = '*';
+    }
+
+    for mut row in rows {
+        while row.last() == Some(&'.') {
+            row.pop();
+        }
+        if row.is_empty() {
+            row.push('.');
+        }
+        out.extend(row);
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let text = "#Life 1.05\n#D The smallest spaceship.\n#R 23/36\n#P -1 -1\n.*\n..*\n***\n";
+        let pattern = parse(text).unwrap();
+        assert_eq!(pattern.rule, Some(Rule::PRESETS[1].1));
+        assert_eq!(pattern.comments, vec!["The smallest spaceship."]);
+        assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(write(&pattern), text);
+    }
+
+    #[test]
+    fn joins_blocks() {
+        let pattern = parse("#Life 1.05\n#P 0 0\n*\n#P 5 -2\n.*\n").unwrap();
+        assert_eq!((pattern.width, pattern.height), (7, 3));
+        assert_eq!(pattern.cells, vec![(6, 0), (0, 2)]);
+    }
+}
@@ -0,0 +1,61 @@
+//! Life 1.06 patterns: one `x y` coordinate pair per live cell.
+
+use super::{ParsePatternError, Pattern};
+
+pub const HEADER: &str = "#Life 1.06";
+
+pub fn parse(text: &str) -> Result<Pattern, ParsePatternError> {
+    let mut cells = Vec::new();
+
+    for (number, line) in text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+    {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut coordinates = line.split_whitespace().map(str::parse::<i64>);
+        match (coordinates.next(), coordinates.next(), coordinates.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => cells.push((x, y)),
+            _ => {
+                return Err(ParsePatternError::new(
+                    number,
+                    format!("expected \"x y\", found \"{line}\""),
+                ));
+            }
+        }
+    }
+
+    Ok(Pattern::from_cells(cells))
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = format!("{HEADER}\n");
+
+    for &(x, y) in &pattern.cells {
+        out.push_str(&format!("{x} {y}\n"));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let text = "#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2\n";
+        let pattern = parse(text).unwrap();
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(write(&pattern), text);
+    }
+
+    #[test]
+    fn moves_negative_coordinates_to_the_origin() {
+        let pattern = parse("#Life 1.06\n-3 -1\n-2 -1\n").unwrap();
+        assert_eq!(pattern.cells, vec![(0, 0), (1, 0)]);
+    }
+}
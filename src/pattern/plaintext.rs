@@ -0,0 +1,88 @@
+//! Plaintext (`.cells`) patterns: `!` comment lines followed by rows of
+//! `.` for dead and `O` for live cells.
+
+use super::{ParsePatternError, Pattern};
+
+pub fn parse(text: &str) -> Result<Pattern, ParsePatternError> {
+    let mut pattern = Pattern::default();
+    let mut y = 0;
+
+    for (number, line) in text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim_end()))
+    {
+        if let Some(comment) = line.strip_prefix('!') {
+            if let Some(name) = comment.strip_prefix("Name:") {
+                pattern.name = Some(name.trim().to_string());
+            } else if let Some(author) = comment.strip_prefix("Author:") {
+                pattern.author = Some(author.trim().to_string());
+            } else {
+                pattern.comments.push(comment.trim().to_string());
+            }
+            continue;
+        }
+
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                'O' | 'o' | '*' => pattern.cells.push((x as i64, y)),
+                '.' => {}
+                c => {
+                    return Err(ParsePatternError::new(
+                        number,
+                        format!("unexpected '{c}' in pattern row"),
+                    ));
+                }
+            }
+        }
+
+        pattern.width = pattern.width.max(line.chars().count() as u64);
+        y += 1;
+    }
+
+    pattern.height = y as u64;
+    Ok(pattern)
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = String::new();
+
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("!Name: {name}\n"));
+    }
+    if let Some(author) = &pattern.author {
+        out.push_str(&format!("!Author: {author}\n"));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("!{comment}\n"));
+    }
+
+    let (width, height) = (pattern.width as usize, pattern.height as usize);
+    let mut rows = vec![vec!['.'; width]; height];
+    for &(x, y) in &pattern.cells {
+        rows[y as usize][x as usize] = 'O';
+    }
+
+    for row in rows {
+        out.extend(row);
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let text =
+            "!Name: Glider\n!Author: Richard K. Guy\n!The smallest spaceship.\n.O.\n..O\nOOO\n";
+        let pattern = parse(text).unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.author.as_deref(), Some("Richard K. Guy"));
+        assert_eq!(pattern.comments, vec!["The smallest spaceship."]);
+        assert_eq!(write(&pattern), text);
+    }
+}
@@ -0,0 +1,288 @@
+//! Run Length Encoded (`.rle`) patterns, the format used by LifeWiki and
+//! Golly.
+
+use std::collections::BTreeMap;
+
+use super::{ParsePatternError, Pattern};
+use crate::topology::Topology;
+
+/// Longest line `write` produces, as recommended by the format.
+const MAX_LINE_LENGTH: usize = 70;
+
+pub fn parse(text: &str) -> Result<Pattern, ParsePatternError> {
+    let mut pattern = Pattern::default();
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+    let mut has_header = false;
+
+    for (number, line) in lines.by_ref() {
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix('#') {
+            parse_comment(&mut pattern, comment);
+            continue;
+        }
+
+        parse_header(&mut pattern, line).map_err(|m| ParsePatternError::new(number, m))?;
+        has_header = true;
+        break;
+    }
+
+    if !has_header {
+        return Err(ParsePatternError::new(
+            0,
+            "missing \"x = ..., y = ...\" header",
+        ));
+    }
+
+    // Cells must lie inside the header's bounds, which also caps how many
+    // a run can add.
+    let width = i64::try_from(pattern.width).unwrap_or(i64::MAX);
+    let height = i64::try_from(pattern.height).unwrap_or(i64::MAX);
+    let outside = |number| {
+        ParsePatternError::new(
+            number,
+            format!("pattern data runs outside the {width} x {height} header"),
+        )
+    };
+
+    let (mut x, mut y): (i64, i64) = (0, 0);
+    let mut run: Option<i64> = None;
+
+    'body: for (number, line) in lines {
+        if line.starts_with('#') {
+            continue;
+        }
+
+        for c in line.chars() {
+            match c {
+                '0'..='9' => {
+                    let digit = i64::from(c as u8 - b'0');
+                    let count = run
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(digit));
+                    run =
+                        Some(count.ok_or_else(|| {
+                            ParsePatternError::new(number, "run count is too large")
+                        })?);
+                }
+                'b' | '.' => {
+                    x = x
+                        .checked_add(run.take().unwrap_or(1))
+                        .ok_or_else(|| outside(number))?;
+                }
+                'o' | 'A'..='X' => {
+                    let end = x
+                        .checked_add(run.take().unwrap_or(1))
+                        .filter(|&end| end <= width && y < height)
+                        .ok_or_else(|| outside(number))?;
+                    pattern.cells.extend((x..end).map(|x| (x, y)));
+                    x = end;
+                }
+                '$' => {
+                    y = y
+                        .checked_add(run.take().unwrap_or(1))
+                        .ok_or_else(|| outside(number))?;
+                    x = 0;
+                }
+                '!' => break 'body,
+                c if c.is_whitespace() => {}
+                c => {
+                    return Err(ParsePatternError::new(
+                        number,
+                        format!("unexpected '{c}' in pattern data"),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(pattern)
+}
+
+fn parse_comment(pattern: &mut Pattern, comment: &str) {
+    let mut chars = comment.chars();
+    let kind = chars.next();
+    let text = chars.as_str().trim().to_string();
+
+    match kind {
+        Some('N') => pattern.name = Some(text),
+        Some('O') => pattern.author = Some(text),
+        Some('C' | 'c') => pattern.comments.push(text),
+        _ => {}
+    }
+}
+
+/// Parses `x = m, y = n[, rule = abc]`. The rule goes last because a
+/// bounded-grid suffix such as `B3/S23:T50,50` contains a comma itself.
+fn parse_header(pattern: &mut Pattern, line: &str) -> Result<(), String> {
+    let (dimensions, rule) = match line.find("rule") {
+        Some(i) => (&line[..i], Some(&line[i + "rule".len()..])),
+        None => (line, None),
+    };
+
+    let mut width = None;
+    let mut height = None;
+
+    for field in dimensions
+        .split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+    {
+        let (key, value) = field
+            .split_once('=')
+            .ok_or_else(|| format!("expected \"key = value\" in header, found \"{field}\""))?;
+        let value: u64 = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("\"{}\" is not a valid size", value.trim()))?;
+
+        match key.trim() {
+            "x" => width = Some(value),
+            "y" => height = Some(value),
+            key => return Err(format!("unknown header field \"{key}\"")),
+        }
+    }
+
+    pattern.width = width.ok_or("header has no x size")?;
+    pattern.height = height.ok_or("header has no y size")?;
+
+    if let Some(rule) = rule {
+        let rule = rule
+            .trim_start()
+            .strip_prefix('=')
+            .ok_or("expected \"rule = ...\" in header")?;
+        let (rule, grid) = match rule.split_once(':') {
+            Some((rule, grid)) => (rule, Some(grid)),
+            None => (rule, None),
+        };
+
+        pattern.rule = Some(rule.parse().map_err(|e| format!("invalid rule: {e}"))?);
+        pattern.bounded_grid = grid
+            .map(Topology::from_golly)
+            .transpose()
+            .map_err(|e| format!("invalid bounded grid: {e}"))?;
+    }
+
+    Ok(())
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = String::new();
+
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("#N {name}\n"));
+    }
+    if let Some(author) = &pattern.author {
+        out.push_str(&format!("#O {author}\n"));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#C {comment}\n"));
+    }
+
+    out.push_str(&format!("x = {}, y = {}", pattern.width, pattern.height));
+    if let Some(rule) = pattern.rule {
+        out.push_str(&format!(", rule = {rule}"));
+
+        if let Some(grid) = pattern
+            .bounded_grid
+            .and_then(|(topology, width, height)| topology.to_golly(width, height))
+        {
+            out.push_str(&format!(":{grid}"));
+        }
+    }
+    out.push('\n');
+
+    let mut rows: BTreeMap<i64, Vec<i64>> = BTreeMap::new();
+    for &(x, y) in &pattern.cells {
+        rows.entry(y).or_default().push(x);
+    }
+
+    let mut items = Vec::new();
+    let mut previous_y = 0;
+
+    for (y, mut xs) in rows {
+        xs.sort_unstable();
+        xs.dedup();
+
+        if y > previous_y {
+            items.push(run(y - previous_y, '$'));
+        }
+        previous_y = y;
+
+        let mut cursor = 0;
+        let mut i = 0;
+        while i < xs.len() {
+            let start = xs[i];
+            while i + 1 < xs.len() && xs[i + 1] == xs[i] + 1 {
+                i += 1;
+            }
+
+            if start > cursor {
+                items.push(run(start - cursor, 'b'));
+            }
+            items.push(run(xs[i] - start + 1, 'o'));
+            cursor = xs[i] + 1;
+            i += 1;
+        }
+    }
+    items.push("!".to_string());
+
+    let mut line = String::new();
+    for item in items {
+        if line.len() + item.len() > MAX_LINE_LENGTH {
+            out.push_str(&line);
+            out.push('\n');
+            line.clear();
+        }
+        line.push_str(&item);
+    }
+    out.push_str(&line);
+    out.push('\n');
+
+    out
+}
+
+fn run(count: i64, tag: char) -> String {
+    if count == 1 {
+        tag.to_string()
+    } else {
+        format!("{count}{tag}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cells_inside_the_header() {
+        let pattern = parse("x = 3, y = 2\nbo$3o!").unwrap();
+        assert_eq!((pattern.width, pattern.height), (3, 2));
+        assert_eq!(pattern.cells, vec![(1, 0), (0, 1), (1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn rejects_cells_outside_the_header() {
+        assert!(parse("x = 1, y = 1\n3o!").is_err());
+        assert!(parse("x = 3, y = 1\no$o!").is_err());
+        assert!(parse("x = 3, y = 3\n4000000000o!").is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_positions() {
+        assert!(parse("x = 1, y = 1\n9223372036854775807b9223372036854775807b!").is_err());
+        assert!(parse("x = 1, y = 1\n9223372036854775807$9223372036854775807$o!").is_err());
+    }
+
+    #[test]
+    fn round_trips() {
+        let text = "#N Glider\nx = 3, y = 3, rule = B3/S23:T8,8\nbo$2bo$3o!\n";
+        assert_eq!(write(&parse(text).unwrap()), text);
+    }
+}
@@ -0,0 +1,233 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use bevy::prelude::Resource;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Outer-totalistic birth/survival rule, e.g. `B3/S23` for Conway's Life.
+///
+/// Bit `n` of `birth` (or `survival`) is set when a dead (or live) cell with
+/// `n` live neighbours is alive in the next generation.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: u16,
+    survival: u16,
+}
+
+impl Rule {
+    pub const LIFE: Rule = Rule::from_masks(0b1000, 0b1100);
+
+    /// Well-known rules that can be cycled through at runtime.
+    pub const PRESETS: [(&'static str, Rule); 7] = [
+        ("Life", Rule::LIFE),
+        ("HighLife", Rule::from_masks(0b100_1000, 0b1100)),
+        (
+            "Day & Night",
+            Rule::from_masks(0b1_1100_1000, 0b1_1101_1000),
+        ),
+        ("Seeds", Rule::from_masks(0b100, 0)),
+        (
+            "Life without Death",
+            Rule::from_masks(0b1000, 0b1_1111_1111),
+        ),
+        ("2x2", Rule::from_masks(0b100_1000, 0b10_0110)),
+        ("Maze", Rule::from_masks(0b1000, 0b11_1110)),
+    ];
+
+    const fn from_masks(birth: u16, survival: u16) -> Self {
+        Rule { birth, survival }
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        Rule::PRESETS
+            .iter()
+            .find(|(_, rule)| rule == self)
+            .map(|(name, _)| *name)
+    }
+
+    /// The rule in the older survival/birth notation, e.g. `23/3`.
+    pub fn sb_notation(&self) -> String {
+        format!("{}/{}", digits(self.survival), digits(self.birth))
+    }
+
+    pub fn next_state(&self, is_alive: bool, neighbours: u8) -> bool {
+        let mask = if is_alive { self.survival } else { self.birth };
+        mask & 1 << neighbours != 0
+    }
+
+    /// Returns the preset after this one, wrapping around; rules that are
+    /// not presets move to the first preset.
+    pub fn next_preset(&self) -> Rule {
+        let position = Rule::PRESETS.iter().position(|(_, rule)| rule == self);
+        let next = position.map_or(0, |i| (i + 1) % Rule::PRESETS.len());
+        Rule::PRESETS[next].1
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::LIFE
+    }
+}
+
+fn digits(mask: u16) -> String {
+    (0..=8)
+        .filter(|n| mask & 1 << n != 0)
+        .map(|n| char::from(b'0' + n))
+        .collect()
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "B{}/S{}", digits(self.birth), digits(self.survival))
+    }
+}
+
+/// Rules are stored in their B/S notation, which is how people read them.
+impl Serialize for Rule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseRuleError {
+    MissingSlash,
+    UnknownSection(String),
+    RepeatedSection(char),
+    MixedNotation,
+    InvalidNeighbourCount(char),
+}
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseRuleError::MissingSlash => {
+                write!(f, "expected a rule like \"B3/S23\" or \"23/3\"")
+            }
+            ParseRuleError::UnknownSection(section) => {
+                write!(
+                    f,
+                    "\"{section}\" is neither a B (birth) nor an S (survival) section"
+                )
+            }
+            ParseRuleError::RepeatedSection(letter) => {
+                write!(f, "the {letter} section appears more than once")
+            }
+            ParseRuleError::MixedNotation => {
+                write!(f, "either both sections or neither must start with B/S")
+            }
+            ParseRuleError::InvalidNeighbourCount(c) => {
+                write!(f, "'{c}' is not a neighbour count between 0 and 8")
+            }
+        }
+    }
+}
+
+impl Error for ParseRuleError {}
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    /// Accepts `B3/S23` notation in either section order and the older
+    /// `S/B` digit-only notation such as `23/3`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (first, second) = s
+            .trim()
+            .split_once('/')
+            .ok_or(ParseRuleError::MissingSlash)?;
+
+        let labelled = |section: &str| section.starts_with(|c: char| c.is_ascii_alphabetic());
+
+        let (birth, survival) = match (labelled(first), labelled(second)) {
+            (false, false) => (second, first),
+            (true, true) => {
+                let mut birth = None;
+                let mut survival = None;
+
+                for section in [first, second] {
+                    let (slot, letter) = match section.chars().next() {
+                        Some('B' | 'b') => (&mut birth, 'B'),
+                        Some('S' | 's') => (&mut survival, 'S'),
+                        _ => return Err(ParseRuleError::UnknownSection(section.to_string())),
+                    };
+
+                    if slot.replace(&section[1..]).is_some() {
+                        return Err(ParseRuleError::RepeatedSection(letter));
+                    }
+                }
+
+                (birth.unwrap_or_default(), survival.unwrap_or_default())
+            }
+            _ => return Err(ParseRuleError::MixedNotation),
+        };
+
+        let mask = |digits: &str| {
+            digits
+                .chars()
+                .try_fold(0u16, |mask, c| match c.to_digit(10) {
+                    Some(n) if n <= 8 => Ok(mask | 1 << n),
+                    _ => Err(ParseRuleError::InvalidNeighbourCount(c)),
+                })
+        };
+
+        Ok(Rule::from_masks(mask(birth)?, mask(survival)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bs_notation() {
+        assert_eq!("B3/S23".parse(), Ok(Rule::LIFE));
+        assert_eq!("S23/B3".parse(), Ok(Rule::LIFE));
+        assert_eq!(" b3/s23 ".parse(), Ok(Rule::LIFE));
+        assert_eq!("B36/S23".parse::<Rule>().unwrap().name(), Some("HighLife"));
+        assert_eq!("B2/S".parse::<Rule>().unwrap().name(), Some("Seeds"));
+    }
+
+    #[test]
+    fn parses_sb_notation() {
+        assert_eq!("23/3".parse(), Ok(Rule::LIFE));
+        assert_eq!("/2".parse::<Rule>().unwrap().name(), Some("Seeds"));
+    }
+
+    #[test]
+    fn round_trips_every_preset() {
+        for (_, rule) in Rule::PRESETS {
+            assert_eq!(rule.to_string().parse(), Ok(rule));
+            assert_eq!(rule.sb_notation().parse(), Ok(rule));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        assert_eq!("B3S23".parse::<Rule>(), Err(ParseRuleError::MissingSlash));
+        assert_eq!(
+            "B3/X23".parse::<Rule>(),
+            Err(ParseRuleError::UnknownSection("X23".to_string()))
+        );
+        assert_eq!(
+            "B3/B23".parse::<Rule>(),
+            Err(ParseRuleError::RepeatedSection('B'))
+        );
+        assert_eq!("B3/23".parse::<Rule>(), Err(ParseRuleError::MixedNotation));
+        assert_eq!(
+            "B39/S23".parse::<Rule>(),
+            Err(ParseRuleError::InvalidNeighbourCount('9'))
+        );
+        assert_eq!(
+            "23/3x".parse::<Rule>(),
+            Err(ParseRuleError::InvalidNeighbourCount('x'))
+        );
+    }
+}
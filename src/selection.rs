@@ -0,0 +1,304 @@
+//! Rectangular regions of a universe: copying them out as patterns and
+//! working out the cell writes that clearing, pasting over or moving them
+//! makes.
+
+use std::fmt;
+
+use crate::{
+    pattern::{Pattern, Symmetry},
+    universe::{Bounds, Universe},
+};
+
+/// How pasted cells combine with the cells already on the board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PasteMode {
+    /// Live pasted cells are added; nothing is killed.
+    #[default]
+    Or,
+    /// Live pasted cells toggle the cells under them.
+    Xor,
+    /// The pasted area replaces the board, dead cells included.
+    Copy,
+}
+
+impl PasteMode {
+    pub fn next(self) -> PasteMode {
+        match self {
+            PasteMode::Or => PasteMode::Xor,
+            PasteMode::Xor => PasteMode::Copy,
+            PasteMode::Copy => PasteMode::Or,
+        }
+    }
+}
+
+impl fmt::Display for PasteMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PasteMode::Or => "OR",
+            PasteMode::Xor => "XOR",
+            PasteMode::Copy => "copy",
+        })
+    }
+}
+
+/// Live cells of `universe` inside `area`.
+pub fn live_cells_in(universe: &dyn Universe, area: &Bounds) -> Vec<(i64, i64)> {
+    // Scan whichever is smaller: the area or the whole population.
+    if area.width().saturating_mul(area.height()) <= universe.population() as u64 {
+        (area.min_y..=area.max_y)
+            .flat_map(|y| (area.min_x..=area.max_x).map(move |x| (x, y)))
+            .filter(|&(x, y)| universe.get(x, y))
+            .collect()
+    } else {
+        let mut cells = universe.live_cells();
+        cells.retain(|&(x, y)| area.contains(x, y));
+        cells
+    }
+}
+
+/// The contents of `area` as a pattern the size of the area, so that empty
+/// margins survive a round trip through the clipboard.
+pub fn copy(universe: &dyn Universe, area: &Bounds) -> Pattern {
+    let mut cells: Vec<_> = live_cells_in(universe, area)
+        .into_iter()
+        .map(|(x, y)| (x - area.min_x, y - area.min_y))
+        .collect();
+    cells.sort_by_key(|&(x, y)| (y, x));
+
+    Pattern {
+        width: area.width(),
+        height: area.height(),
+        cells,
+        ..Pattern::default()
+    }
+}
+
+/// The writes that kill every live cell in `area`.
+pub fn clear(universe: &dyn Universe, area: &Bounds) -> Vec<(i64, i64, bool)> {
+    live_cells_in(universe, area)
+        .into_iter()
+        .map(|(x, y)| (x, y, false))
+        .collect()
+}
+
+/// The area `pattern` covers with its top-left corner at `(x, y)`, or
+/// `None` if it is empty.
+pub fn pasted_area(pattern: &Pattern, x: i64, y: i64) -> Option<Bounds> {
+    (pattern.width > 0 && pattern.height > 0).then(|| Bounds {
+        min_x: x,
+        min_y: y,
+        max_x: x + pattern.width as i64 - 1,
+        max_y: y + pattern.height as i64 - 1,
+    })
+}
+
+/// The writes that paste `pattern` with its top-left corner at `(x, y)`.
+pub fn paste(
+    universe: &dyn Universe,
+    pattern: &Pattern,
+    x: i64,
+    y: i64,
+    mode: PasteMode,
+) -> Vec<(i64, i64, bool)> {
+    let pasted = pattern.cells.iter().map(|&(dx, dy)| (x + dx, y + dy));
+
+    match mode {
+        PasteMode::Or => pasted.map(|(x, y)| (x, y, true)).collect(),
+        PasteMode::Xor => pasted.map(|(x, y)| (x, y, !universe.get(x, y))).collect(),
+        PasteMode::Copy => {
+            let Some(area) = pasted_area(pattern, x, y) else {
+                return Vec::new();
+            };
+            clear(universe, &area)
+                .into_iter()
+                .chain(pasted.map(|(x, y)| (x, y, true)))
+                .collect()
+        }
+    }
+}
+
+/// The writes that turn or mirror the contents of `area` about its centre,
+/// along with the area they end up in.
+pub fn transform(
+    universe: &dyn Universe,
+    area: &Bounds,
+    symmetry: Symmetry,
+) -> (Vec<(i64, i64, bool)>, Bounds) {
+    let pattern = copy(universe, area).transformed(symmetry);
+    let (width, height) = (area.width() as i64, area.height() as i64);
+    let x = area.min_x + (width - pattern.width as i64) / 2;
+    let y = area.min_y + (height - pattern.height as i64) / 2;
+    move_block(universe, area, &pattern, x, y)
+}
+
+/// The writes that shift the contents of `area` by `(dx, dy)`, along with
+/// the area they end up in.
+pub fn nudge(
+    universe: &dyn Universe,
+    area: &Bounds,
+    dx: i64,
+    dy: i64,
+) -> (Vec<(i64, i64, bool)>, Bounds) {
+    let pattern = copy(universe, area);
+    move_block(universe, area, &pattern, area.min_x + dx, area.min_y + dy)
+}
+
+/// Clears `area` and pastes `pattern` at `(x, y)` as a solid block, so that
+/// it replaces whatever it lands on.
+fn move_block(
+    universe: &dyn Universe,
+    area: &Bounds,
+    pattern: &Pattern,
+    x: i64,
+    y: i64,
+) -> (Vec<(i64, i64, bool)>, Bounds) {
+    let mut writes = clear(universe, area);
+    writes.extend(paste(universe, pattern, x, y, PasteMode::Copy));
+    (writes, pasted_area(pattern, x, y).unwrap_or(*area))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pattern::rle, sparse::SparseGrid, universe::sorted_cells};
+
+    fn board(cells: &[(i64, i64)]) -> SparseGrid {
+        let mut board = SparseGrid::new();
+        for &(x, y) in cells {
+            board.set(x, y, true);
+        }
+        board
+    }
+
+    fn apply(universe: &mut dyn Universe, writes: &[(i64, i64, bool)]) {
+        for &(x, y, is_alive) in writes {
+            universe.set(x, y, is_alive);
+        }
+    }
+
+    /// A 3x2 pattern with a live top-left and bottom-right corner.
+    fn corners() -> Pattern {
+        Pattern {
+            width: 3,
+            height: 2,
+            cells: vec![(0, 0), (2, 1)],
+            ..Pattern::default()
+        }
+    }
+
+    #[test]
+    fn or_paste_only_adds_cells() {
+        let mut universe = board(&[(10, 10), (11, 10)]);
+        let writes = paste(&universe, &corners(), 10, 10, PasteMode::Or);
+        apply(&mut universe, &writes);
+        assert_eq!(sorted_cells(&universe), vec![(10, 10), (11, 10), (12, 11)]);
+    }
+
+    #[test]
+    fn xor_paste_toggles_cells() {
+        let mut universe = board(&[(10, 10), (11, 10)]);
+        let writes = paste(&universe, &corners(), 10, 10, PasteMode::Xor);
+        apply(&mut universe, &writes);
+        assert_eq!(sorted_cells(&universe), vec![(11, 10), (12, 11)]);
+    }
+
+    #[test]
+    fn copy_paste_clears_dead_cells_in_the_area() {
+        // (11, 10) and (10, 11) are under dead pasted cells; (13, 10) is
+        // just outside the area.
+        let mut universe = board(&[(11, 10), (10, 11), (13, 10)]);
+        let writes = paste(&universe, &corners(), 10, 10, PasteMode::Copy);
+        apply(&mut universe, &writes);
+        assert_eq!(sorted_cells(&universe), vec![(10, 10), (12, 11), (13, 10)]);
+    }
+
+    #[test]
+    fn copied_margins_survive_rle() {
+        let universe = board(&[(5, 6), (6, 6)]);
+        let area = Bounds {
+            min_x: 3,
+            min_y: 4,
+            max_x: 8,
+            max_y: 9,
+        };
+
+        let pattern = rle::parse(&rle::write(&copy(&universe, &area))).unwrap();
+        assert_eq!((pattern.width, pattern.height), (6, 6));
+
+        let mut target = board(&[(20, 20), (25, 25), (26, 26)]);
+        let writes = paste(&target, &pattern, 20, 20, PasteMode::Copy);
+        apply(&mut target, &writes);
+        assert_eq!(sorted_cells(&target), vec![(22, 22), (23, 22), (26, 26)]);
+    }
+
+    /// A 5x2 area at (10, 20) holding an L that no flip or turn maps onto
+    /// itself.
+    fn l_shape() -> (SparseGrid, Bounds) {
+        let area = Bounds {
+            min_x: 10,
+            min_y: 20,
+            max_x: 14,
+            max_y: 21,
+        };
+        (board(&[(10, 20), (10, 21), (11, 21), (12, 21)]), area)
+    }
+
+    #[test]
+    fn turning_back_restores_the_area() {
+        let (mut universe, area) = l_shape();
+        let before = sorted_cells(&universe);
+
+        let (writes, turned) = transform(&universe, &area, Symmetry::RotateClockwise);
+        apply(&mut universe, &writes);
+        assert_eq!((turned.width(), turned.height()), (2, 5));
+        assert_eq!(
+            sorted_cells(&universe),
+            vec![(11, 19), (11, 20), (11, 21), (12, 19)]
+        );
+
+        let (writes, restored) = transform(&universe, &turned, Symmetry::RotateCounterclockwise);
+        apply(&mut universe, &writes);
+        assert_eq!(restored, area);
+        assert_eq!(sorted_cells(&universe), before);
+    }
+
+    #[test]
+    fn flipping_twice_restores_the_area() {
+        for symmetry in [Symmetry::FlipHorizontal, Symmetry::FlipVertical] {
+            let (mut universe, area) = l_shape();
+            let before = sorted_cells(&universe);
+
+            let (writes, flipped) = transform(&universe, &area, symmetry);
+            apply(&mut universe, &writes);
+            assert_eq!(flipped, area);
+            assert_ne!(sorted_cells(&universe), before, "{symmetry:?}");
+
+            let (writes, _) = transform(&universe, &flipped, symmetry);
+            apply(&mut universe, &writes);
+            assert_eq!(sorted_cells(&universe), before, "{symmetry:?}");
+        }
+    }
+
+    #[test]
+    fn nudge_moves_the_area() {
+        let (mut universe, area) = l_shape();
+        universe.set(16, 21, true);
+
+        let (writes, moved) = nudge(&universe, &area, 3, -1);
+        apply(&mut universe, &writes);
+        assert_eq!(
+            moved,
+            Bounds {
+                min_x: 13,
+                min_y: 19,
+                max_x: 17,
+                max_y: 20,
+            }
+        );
+        // The cell at (16, 21) is outside the moved area and stays put.
+        assert_eq!(
+            sorted_cells(&universe),
+            vec![(13, 19), (13, 20), (14, 20), (15, 20), (16, 21)]
+        );
+    }
+}
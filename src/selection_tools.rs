@@ -0,0 +1,403 @@
+//! Selecting an area of the board, copying it to and pasting it from the
+//! clipboard, turning and nudging it, and stamping patterns from the
+//! library.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_life_game::{
+    config::GameConfig,
+    pattern::{self, rle, Pattern, Symmetry},
+    rule::Rule,
+    selection::{self, PasteMode},
+    undo::UndoLog,
+    universe::{Bounds, Universe},
+};
+
+use crate::{area_rect, cell_center, cursor_cell, Board, GameSet, SimulationStats, Viewport};
+
+/// Commands that copy to and paste from the system clipboard on macOS,
+/// Wayland, X11 and Windows, tried in turn.
+const CLIPBOARD_COMMANDS: [(&[&str], &[&str]); 4] = [
+    (&["pbcopy"], &["pbpaste"]),
+    (&["wl-copy"], &["wl-paste", "--no-newline"]),
+    (
+        &["xclip", "-selection", "clipboard"],
+        &["xclip", "-selection", "clipboard", "-o"],
+    ),
+    (
+        &["clip"],
+        &["powershell", "-NoProfile", "-Command", "Get-Clipboard"],
+    ),
+];
+
+/// The selected rectangle of the board, in universe coordinates.
+#[derive(Resource, Default)]
+pub(crate) struct Selection(pub(crate) Option<Bounds>);
+
+/// A pattern following the cursor until it is pasted, and how it will be
+/// merged with the board. Patterns picked from the library are stamped
+/// again on every click until dropped.
+#[derive(Resource, Default)]
+pub(crate) struct Paste {
+    pub(crate) pattern: Option<Pattern>,
+    pub(crate) mode: PasteMode,
+    pub(crate) repeat: bool,
+}
+
+/// The last copied pattern as RLE, used when the system clipboard cannot
+/// be reached.
+#[derive(Resource, Default)]
+struct Clipboard(String);
+
+pub(crate) struct SelectionToolsPlugin;
+
+impl Plugin for SelectionToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Selection>()
+            .init_resource::<Paste>()
+            .init_resource::<Clipboard>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        select_cells,
+                        edit_selection,
+                        transform_selection,
+                        place_paste,
+                    )
+                        .chain()
+                        .after(GameSet::Edit)
+                        .before(GameSet::Advance),
+                    draw_selection.after(GameSet::Advance),
+                ),
+            );
+    }
+}
+
+/// Drags out a selection with Shift and the left mouse button.
+#[allow(clippy::too_many_arguments)]
+fn select_cells(
+    mouse: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    interactions: Query<&Interaction>,
+    mut selection: ResMut<Selection>,
+    paste: Res<Paste>,
+    viewport: Res<Viewport>,
+    config: Res<GameConfig>,
+    mut anchor: Local<Option<(i64, i64)>>,
+) {
+    if !mouse.pressed(MouseButton::Left) {
+        *anchor = None;
+        return;
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let cell = cursor_cell(window, camera, camera_transform, &config)
+        .map(|(x, y)| (viewport.x + x as i64, viewport.y + y as i64));
+
+    if mouse.just_pressed(MouseButton::Left) {
+        let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let on_ui = interactions.iter().any(|i| *i != Interaction::None);
+        *anchor = cell.filter(|_| shift && !on_ui && paste.pattern.is_none());
+    }
+
+    if let (Some(anchor), Some(cell)) = (*anchor, cell) {
+        selection.0 = Bounds::from_cells([anchor, cell]);
+    }
+}
+
+/// Applies cell writes to the board as one undoable edit.
+fn edit_board(
+    undo: &mut UndoLog,
+    board: &mut dyn Universe,
+    writes: Vec<(i64, i64, bool)>,
+    rule: Rule,
+    generation: u64,
+) {
+    undo.finish_run(board, rule, generation);
+    for (x, y, is_alive) in writes {
+        undo.set(board, x, y, is_alive);
+    }
+    undo.commit(rule, generation);
+}
+
+fn copy_to_system_clipboard(text: &str) -> bool {
+    CLIPBOARD_COMMANDS.iter().any(|(copy, _)| {
+        let child = Command::new(copy[0])
+            .args(&copy[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else {
+            return false;
+        };
+
+        let written = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        child.wait().is_ok_and(|status| status.success()) && written
+    })
+}
+
+fn paste_from_system_clipboard() -> Option<String> {
+    CLIPBOARD_COMMANDS.iter().find_map(|(_, paste)| {
+        let output = Command::new(paste[0])
+            .args(&paste[1..])
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    })
+}
+
+/// Copies (Ctrl+C), cuts (Ctrl+X) and clears (Delete) the selection, and
+/// picks up the clipboard for pasting on Ctrl+V. The clipboard holds RLE,
+/// so patterns can be copied to and from other Life programs. Escape drops
+/// the paste, or else the selection, and M cycles the paste mode.
+#[allow(clippy::too_many_arguments)]
+fn edit_selection(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut board: ResMut<Board>,
+    mut undo: ResMut<UndoLog>,
+    mut selection: ResMut<Selection>,
+    mut paste: ResMut<Paste>,
+    mut clipboard: ResMut<Clipboard>,
+    stats: Res<SimulationStats>,
+    rule: Res<Rule>,
+) {
+    let ctrl = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+
+    if keyboard.just_pressed(KeyCode::Escape) && paste.pattern.take().is_none() {
+        selection.0 = None;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyM) {
+        paste.mode = paste.mode.next();
+        info!("paste mode: {}", paste.mode);
+    }
+
+    if ctrl && keyboard.just_pressed(KeyCode::KeyV) {
+        // Prefer the system clipboard, unless it holds something else.
+        let text = paste_from_system_clipboard()
+            .filter(|text| !text.trim().is_empty() && pattern::parse(text).is_ok())
+            .unwrap_or_else(|| clipboard.0.clone());
+
+        match pattern::parse(&text) {
+            Ok(pattern) if !pattern.cells.is_empty() => {
+                paste.pattern = Some(pattern);
+                paste.repeat = false;
+            }
+            Ok(_) => info!("nothing to paste"),
+            Err(e) => warn!("cannot paste: {e}"),
+        }
+    }
+
+    let Some(area) = selection.0 else {
+        return;
+    };
+
+    if ctrl && keyboard.any_just_pressed([KeyCode::KeyC, KeyCode::KeyX]) {
+        let pattern = Pattern {
+            rule: Some(*rule),
+            ..selection::copy(board.0.as_ref(), &area)
+        };
+        clipboard.0 = rle::write(&pattern);
+
+        if copy_to_system_clipboard(&clipboard.0) {
+            info!("copied {}x{} cells", area.width(), area.height());
+        } else {
+            info!(
+                "copied {}x{} cells (system clipboard unavailable)",
+                area.width(),
+                area.height()
+            );
+        }
+    }
+
+    let cut = ctrl && keyboard.just_pressed(KeyCode::KeyX);
+    if cut || keyboard.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        let writes = selection::clear(board.0.as_ref(), &area);
+        edit_board(&mut undo, board.0.as_mut(), writes, *rule, stats.generation);
+    }
+}
+
+/// Turns the selection clockwise on `.` and counterclockwise on `,`, and
+/// mirrors it left to right on X and top to bottom on Y, about its centre.
+/// Shift and the arrow keys nudge it by a cell. While pasting, the turns
+/// and mirrors apply to the pattern being pasted instead.
+fn transform_selection(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut board: ResMut<Board>,
+    mut undo: ResMut<UndoLog>,
+    mut selection: ResMut<Selection>,
+    mut paste: ResMut<Paste>,
+    stats: Res<SimulationStats>,
+    rule: Res<Rule>,
+) {
+    if keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    let symmetry = [
+        (KeyCode::Period, Symmetry::RotateClockwise),
+        (KeyCode::Comma, Symmetry::RotateCounterclockwise),
+        (KeyCode::KeyX, Symmetry::FlipHorizontal),
+        (KeyCode::KeyY, Symmetry::FlipVertical),
+    ]
+    .into_iter()
+    .find_map(|(key, symmetry)| keyboard.just_pressed(key).then_some(symmetry));
+
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let nudge = [
+        (KeyCode::ArrowLeft, (-1, 0)),
+        (KeyCode::ArrowRight, (1, 0)),
+        (KeyCode::ArrowUp, (0, -1)),
+        (KeyCode::ArrowDown, (0, 1)),
+    ]
+    .into_iter()
+    .find_map(|(key, step)| (shift && keyboard.just_pressed(key)).then_some(step));
+
+    if let (Some(pattern), Some(symmetry)) = (&mut paste.pattern, symmetry) {
+        *pattern = pattern.transformed(symmetry);
+        return;
+    }
+
+    let Some(area) = selection.0 else {
+        return;
+    };
+    let (writes, moved) = match (symmetry, nudge) {
+        (Some(symmetry), _) => selection::transform(board.0.as_ref(), &area, symmetry),
+        (None, Some((dx, dy))) => selection::nudge(board.0.as_ref(), &area, dx, dy),
+        (None, None) => return,
+    };
+
+    edit_board(&mut undo, board.0.as_mut(), writes, *rule, stats.generation);
+    selection.0 = Some(moved);
+}
+
+/// Pastes the pattern centred under the cursor on a left click, merging it
+/// in the current paste mode and selecting the pasted area. A right click
+/// drops it.
+#[allow(clippy::too_many_arguments)]
+fn place_paste(
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    interactions: Query<&Interaction>,
+    mut board: ResMut<Board>,
+    mut undo: ResMut<UndoLog>,
+    mut paste: ResMut<Paste>,
+    mut selection: ResMut<Selection>,
+    viewport: Res<Viewport>,
+    stats: Res<SimulationStats>,
+    rule: Res<Rule>,
+    config: Res<GameConfig>,
+) {
+    if paste.pattern.is_none() {
+        return;
+    }
+
+    if mouse.just_pressed(MouseButton::Right) {
+        paste.pattern = None;
+        return;
+    }
+
+    let on_ui = interactions.iter().any(|i| *i != Interaction::None);
+    if !mouse.just_pressed(MouseButton::Left) || on_ui {
+        return;
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let Some((x, y)) = cursor_cell(window, camera, camera_transform, &config) else {
+        return;
+    };
+
+    let mode = paste.mode;
+    let pattern = match paste.repeat {
+        true => paste.pattern.clone(),
+        false => paste.pattern.take(),
+    };
+    let Some(pattern) = pattern else {
+        return;
+    };
+    let (x, y) = paste_corner(&pattern, viewport.x + x as i64, viewport.y + y as i64);
+
+    let writes = selection::paste(board.0.as_ref(), &pattern, x, y, mode);
+    edit_board(&mut undo, board.0.as_mut(), writes, *rule, stats.generation);
+    selection.0 = selection::pasted_area(&pattern, x, y);
+}
+
+/// Top-left corner that centres `pattern` on cell `(x, y)`.
+fn paste_corner(pattern: &Pattern, x: i64, y: i64) -> (i64, i64) {
+    (x - pattern.width as i64 / 2, y - pattern.height as i64 / 2)
+}
+
+/// Outlines the selection, and the cells of a pending paste under the
+/// cursor.
+fn draw_selection(
+    mut gizmos: Gizmos,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    selection: Res<Selection>,
+    paste: Res<Paste>,
+    viewport: Res<Viewport>,
+    config: Res<GameConfig>,
+) {
+    if let Some(area) = selection.0 {
+        let (center, size) = area_rect(&area, &viewport, &config);
+        gizmos.rect_2d(center, Rot2::IDENTITY, size, Color::srgb(1.0, 0.8, 0.0));
+    }
+
+    let Some(pattern) = &paste.pattern else {
+        return;
+    };
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let Some((x, y)) = cursor_cell(window, camera, camera_transform, &config) else {
+        return;
+    };
+
+    let (x, y) = paste_corner(pattern, viewport.x + x as i64, viewport.y + y as i64);
+    if let Some(area) = selection::pasted_area(pattern, x, y) {
+        let (center, size) = area_rect(&area, &viewport, &config);
+        gizmos.rect_2d(center, Rot2::IDENTITY, size, Color::srgb(0.0, 0.8, 1.0));
+    }
+
+    let visible = Bounds {
+        min_x: viewport.x,
+        min_y: viewport.y,
+        max_x: viewport.x + config.width as i64 - 1,
+        max_y: viewport.y + config.height as i64 - 1,
+    };
+    let size = Vec2::splat(config.cell_size * 0.8);
+    for &(dx, dy) in &pattern.cells {
+        if visible.contains(x + dx, y + dy) {
+            let center = cell_center(x + dx, y + dy, &viewport, &config);
+            gizmos.rect_2d(
+                center,
+                Rot2::IDENTITY,
+                size,
+                Color::srgba(0.0, 0.8, 1.0, 0.6),
+            );
+        }
+    }
+}
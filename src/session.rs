@@ -0,0 +1,205 @@
+//! Session snapshots: the board with everything needed to resume it,
+//! saved as RON.
+
+use std::{any::Any, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{GameConfig, MAX_BOARD_SIZE},
+    hashlife::{macrocell, HashLife},
+    rule::Rule,
+    topology::Topology,
+    universe::Universe,
+};
+
+/// Version written into session snapshots. Bump it whenever `Snapshot`
+/// changes shape, so that older builds refuse files they cannot read.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Board contents as saved in a snapshot.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SavedBoard {
+    Cells(Vec<(i64, i64)>),
+    /// A HashLife quadtree in Macrocell form, keeping shared subtrees shared.
+    Macrocell(String),
+}
+
+/// Everything needed to resume a session exactly where it was saved.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub config: GameConfig,
+    pub generation: u64,
+    pub rule: Rule,
+    pub topology: Option<Topology>,
+    pub seed: u64,
+    pub viewport: (i64, i64),
+    pub board: SavedBoard,
+}
+
+impl Snapshot {
+    pub fn capture(
+        board: &dyn Universe,
+        rule: Rule,
+        seed: u64,
+        generation: u64,
+        viewport: (i64, i64),
+        config: &GameConfig,
+    ) -> Snapshot {
+        let universe: &dyn Any = board;
+        let saved = match universe.downcast_ref::<HashLife>() {
+            Some(hashlife) => {
+                SavedBoard::Macrocell(macrocell::write(hashlife, &rule, generation, &[]))
+            }
+            None => SavedBoard::Cells(board.live_cells()),
+        };
+
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            config: config.clone(),
+            generation,
+            rule,
+            topology: board.topology(),
+            seed,
+            viewport,
+            board: saved,
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::new().compact_arrays(true))
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_ron()?).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Parses a snapshot along with the universe it describes.
+    pub fn from_ron(text: &str) -> Result<(Snapshot, Box<dyn Universe>), String> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        // Check the version first: a newer snapshot may not parse at all.
+        let Version { version } = ron::from_str(text).map_err(|e| e.to_string())?;
+        if version > SNAPSHOT_VERSION {
+            return Err(format!(
+                "snapshot version {version} is newer than the supported version {SNAPSHOT_VERSION}"
+            ));
+        }
+
+        let snapshot: Snapshot = ron::from_str(text).map_err(|e| e.to_string())?;
+        let (width, height) = (snapshot.config.width, snapshot.config.height);
+        if width.max(height) > MAX_BOARD_SIZE {
+            return Err(format!(
+                "the {width}x{height} board is larger than {MAX_BOARD_SIZE} cells a side"
+            ));
+        }
+
+        let universe: Box<dyn Universe> = match &snapshot.board {
+            SavedBoard::Cells(cells) => {
+                let config = &snapshot.config;
+                let topology = snapshot.topology.unwrap_or(config.topology);
+                let mut universe = config.backend.create(config.width, config.height, topology);
+                for &(x, y) in cells {
+                    universe.set(x, y, true);
+                }
+                universe
+            }
+            SavedBoard::Macrocell(text) => {
+                Box::new(macrocell::parse(text).map_err(|e| e.to_string())?.universe)
+            }
+        };
+
+        Ok((snapshot, universe))
+    }
+
+    /// Reads a snapshot along with the universe it describes.
+    pub fn read(path: &Path) -> Result<(Snapshot, Box<dyn Universe>), String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Snapshot::from_ron(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::{sorted_cells, Backend};
+
+    fn round_trip(backend: Backend) {
+        let config = GameConfig {
+            width: 20,
+            height: 10,
+            backend,
+            topology: Topology::KleinBottle(crate::topology::TwistedEdges::LeftRight),
+            ..GameConfig::default()
+        };
+        let mut board = backend.create(config.width, config.height, config.topology);
+        for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2), (15, 7)] {
+            board.set(x, y, true);
+        }
+        let rule = Rule::PRESETS[1].1;
+
+        let snapshot = Snapshot::capture(board.as_ref(), rule, 42, 99, (-3, 4), &config);
+        let (restored, universe) = Snapshot::from_ron(&snapshot.to_ron().unwrap()).unwrap();
+
+        assert_eq!(restored.board, snapshot.board);
+        assert_eq!(
+            sorted_cells(universe.as_ref()),
+            sorted_cells(board.as_ref())
+        );
+        assert_eq!(universe.topology(), board.topology());
+        assert_eq!(restored.rule, rule);
+        assert_eq!((restored.seed, restored.generation), (42, 99));
+        assert_eq!(restored.viewport, (-3, 4));
+        assert_eq!(restored.config.backend, backend);
+        assert_eq!((restored.config.width, restored.config.height), (20, 10));
+    }
+
+    #[test]
+    fn round_trips_cells() {
+        round_trip(Backend::BitPacked);
+    }
+
+    #[test]
+    fn round_trips_macrocell() {
+        round_trip(Backend::HashLife);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let board = Backend::Sparse.create(0, 0, Topology::Plane);
+        let mut snapshot = Snapshot::capture(
+            board.as_ref(),
+            Rule::LIFE,
+            0,
+            0,
+            (0, 0),
+            &GameConfig::default(),
+        );
+        snapshot.version = SNAPSHOT_VERSION + 1;
+
+        let Err(error) = Snapshot::from_ron(&snapshot.to_ron().unwrap()) else {
+            panic!("a newer snapshot was accepted");
+        };
+        assert!(error.contains("newer"), "{error}");
+    }
+
+    #[test]
+    fn rejects_oversized_boards() {
+        let config = GameConfig {
+            width: MAX_BOARD_SIZE + 1,
+            ..GameConfig::default()
+        };
+        let board = Backend::Sparse.create(0, 0, Topology::Plane);
+        let snapshot = Snapshot::capture(board.as_ref(), Rule::LIFE, 0, 0, (0, 0), &config);
+
+        let Err(error) = Snapshot::from_ron(&snapshot.to_ron().unwrap()) else {
+            panic!("an oversized snapshot was accepted");
+        };
+        assert!(error.contains("larger than"), "{error}");
+    }
+}
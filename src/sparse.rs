@@ -0,0 +1,137 @@
+use bevy::utils::{HashMap, HashSet};
+
+use crate::{
+    rule::Rule,
+    topology::Topology,
+    universe::{StepChanges, Universe},
+};
+
+/// Unbounded universe that stores only the coordinates of live cells, so
+/// patterns can travel arbitrarily far without wrapping into themselves.
+///
+/// Only cells next to a live cell are ever considered for birth, so rules
+/// with `B0` behave as if that birth condition were absent.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SparseGrid {
+    cells: HashSet<(i64, i64)>,
+}
+
+impl SparseGrid {
+    pub fn new() -> Self {
+        SparseGrid::default()
+    }
+}
+
+impl Universe for SparseGrid {
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.cells.contains(&(x, y))
+    }
+
+    fn set(&mut self, x: i64, y: i64, is_alive: bool) {
+        if is_alive {
+            self.cells.insert((x, y));
+        } else {
+            self.cells.remove(&(x, y));
+        }
+    }
+
+    fn step(&mut self, rule: &Rule) {
+        let mut neighbours: HashMap<(i64, i64), u8> = HashMap::default();
+        neighbours.reserve(self.cells.len() * 9);
+
+        for &(x, y) in &self.cells {
+            neighbours.entry((x, y)).or_default();
+
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if (dx, dy) != (0, 0) {
+                        *neighbours.entry((x + dx, y + dy)).or_default() += 1;
+                    }
+                }
+            }
+        }
+
+        self.cells = neighbours
+            .into_iter()
+            .filter(|(cell, count)| rule.next_state(self.cells.contains(cell), *count))
+            .map(|(cell, _)| cell)
+            .collect();
+    }
+
+    fn step_counting(&mut self, rule: &Rule) -> StepChanges {
+        let before = self.cells.clone();
+        self.step(rule);
+
+        let births = self.cells.difference(&before).count();
+        StepChanges {
+            births,
+            deaths: before.len() + births - self.cells.len(),
+        }
+    }
+
+    fn population(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        self.cells.iter().copied().collect()
+    }
+
+    fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    fn topology(&self) -> Option<Topology> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::universe::sorted_cells;
+
+    #[test]
+    fn glider_travels_without_wrapping() {
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        let mut sparse = SparseGrid::new();
+        for (x, y) in glider {
+            sparse.set(x, y, true);
+        }
+
+        // A glider moves one cell down and to the right every 4 generations.
+        for _ in 0..4000 {
+            sparse.step(&Rule::LIFE);
+        }
+
+        let mut expected: Vec<_> = glider.iter().map(|&(x, y)| (x + 1000, y + 1000)).collect();
+        expected.sort();
+        assert_eq!(sorted_cells(&sparse), expected);
+    }
+
+    #[test]
+    fn step_counting_matches_the_change_in_cells() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut sparse = SparseGrid::new();
+        for y in 0..24 {
+            for x in 0..24 {
+                sparse.set(x, y, rng.gen_bool(0.4));
+            }
+        }
+
+        for (_, rule) in Rule::PRESETS {
+            for generation in 1..=8 {
+                let before = sparse.cells.clone();
+                let changes = sparse.step_counting(&rule);
+
+                let expected = StepChanges {
+                    births: sparse.cells.difference(&before).count(),
+                    deaths: before.difference(&sparse.cells).count(),
+                };
+                assert_eq!(changes, expected, "{rule} generation {generation}");
+            }
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::{error::Error, fmt};
+
+use serde::{Deserialize, Serialize};
+
+/// How the edges of a bounded grid are glued together.
+///
+/// The names follow Golly's bounded grids: `P` plane, `T` torus, `K` Klein
+/// bottle and `C` cross-surface. Cylinders are tori with one pair of edges
+/// left dead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Topology {
+    /// Cells outside the board are permanently dead.
+    Plane,
+    #[default]
+    Torus,
+    /// Left and right edges are joined; top and bottom are dead.
+    HorizontalCylinder,
+    /// Top and bottom edges are joined; left and right are dead.
+    VerticalCylinder,
+    /// A torus with one pair of edges joined in reverse.
+    KleinBottle(TwistedEdges),
+    /// Both pairs of edges joined in reverse (a projective plane).
+    CrossSurface,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TwistedEdges {
+    /// Golly's `K50*,50`: leaving through the top re-enters mirrored at the bottom.
+    TopBottom,
+    /// Golly's `K50,50*`: leaving through the left re-enters mirrored on the right.
+    LeftRight,
+}
+
+#[derive(Clone, Copy)]
+enum Edge {
+    Dead,
+    Joined,
+    Twisted,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 7] = [
+        Topology::Plane,
+        Topology::Torus,
+        Topology::HorizontalCylinder,
+        Topology::VerticalCylinder,
+        Topology::KleinBottle(TwistedEdges::TopBottom),
+        Topology::KleinBottle(TwistedEdges::LeftRight),
+        Topology::CrossSurface,
+    ];
+
+    pub fn next(&self) -> Topology {
+        let i = Topology::ALL.iter().position(|t| t == self).unwrap_or(0);
+        Topology::ALL[(i + 1) % Topology::ALL.len()]
+    }
+
+    /// Returns how the (left/right, top/bottom) edge pairs are glued.
+    fn edges(&self) -> (Edge, Edge) {
+        match self {
+            Topology::Plane => (Edge::Dead, Edge::Dead),
+            Topology::Torus => (Edge::Joined, Edge::Joined),
+            Topology::HorizontalCylinder => (Edge::Joined, Edge::Dead),
+            Topology::VerticalCylinder => (Edge::Dead, Edge::Joined),
+            Topology::KleinBottle(TwistedEdges::TopBottom) => (Edge::Joined, Edge::Twisted),
+            Topology::KleinBottle(TwistedEdges::LeftRight) => (Edge::Twisted, Edge::Joined),
+            Topology::CrossSurface => (Edge::Twisted, Edge::Twisted),
+        }
+    }
+
+    /// Maps a coordinate that may lie up to one cell outside a
+    /// `width` x `height` board back onto the board, or `None` if it falls
+    /// off a dead edge.
+    ///
+    /// The top/bottom edges are crossed before the left/right edges, so a
+    /// diagonal step out of a corner is resolved one edge at a time.
+    pub fn resolve(
+        &self,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+    ) -> Option<(usize, usize)> {
+        let (w, h) = (width as isize, height as isize);
+        let (left_right, top_bottom) = self.edges();
+        let (mut x, mut y) = (x, y);
+
+        if !(0..h).contains(&y) {
+            match top_bottom {
+                Edge::Dead => return None,
+                Edge::Joined => y = y.rem_euclid(h),
+                Edge::Twisted => {
+                    y = y.rem_euclid(h);
+                    x = w - 1 - x;
+                }
+            }
+        }
+
+        if !(0..w).contains(&x) {
+            match left_right {
+                Edge::Dead => return None,
+                Edge::Joined => x = x.rem_euclid(w),
+                Edge::Twisted => {
+                    x = x.rem_euclid(w);
+                    y = h - 1 - y;
+                }
+            }
+        }
+
+        Some((x as usize, y as usize))
+    }
+
+    /// Formats the grid as a Golly bounded-grid spec such as `T50,50` or
+    /// `K50*,50`. Cylinders have no finite Golly equivalent.
+    pub fn to_golly(&self, width: usize, height: usize) -> Option<String> {
+        let spec = match self {
+            Topology::Plane => format!("P{width},{height}"),
+            Topology::Torus => format!("T{width},{height}"),
+            Topology::KleinBottle(TwistedEdges::TopBottom) => format!("K{width}*,{height}"),
+            Topology::KleinBottle(TwistedEdges::LeftRight) => format!("K{width},{height}*"),
+            Topology::CrossSurface => format!("C{width},{height}"),
+            Topology::HorizontalCylinder | Topology::VerticalCylinder => return None,
+        };
+
+        Some(spec)
+    }
+
+    /// Parses a Golly bounded-grid spec into a topology and board size.
+    /// A single dimension (`T50`) means a square board.
+    pub fn from_golly(spec: &str) -> Result<(Topology, usize, usize), ParseTopologyError> {
+        let spec = spec.trim();
+        let mut chars = spec.chars();
+        let kind = chars.next().ok_or(ParseTopologyError::Empty)?;
+        let dimensions = chars.as_str();
+
+        let (width, height) = dimensions
+            .split_once(',')
+            .unwrap_or((dimensions, dimensions));
+        let (width, width_twisted) = parse_dimension(width)?;
+        let (height, height_twisted) = parse_dimension(height)?;
+
+        let topology = match (kind.to_ascii_uppercase(), width_twisted, height_twisted) {
+            ('P', false, false) => Topology::Plane,
+            ('T', false, false) => Topology::Torus,
+            ('C', false, false) => Topology::CrossSurface,
+            ('K', true, false) => Topology::KleinBottle(TwistedEdges::TopBottom),
+            ('K', false, true) => Topology::KleinBottle(TwistedEdges::LeftRight),
+            ('K', ..) => return Err(ParseTopologyError::KleinBottleTwist),
+            ('P' | 'T' | 'C', ..) => return Err(ParseTopologyError::UnexpectedTwist(kind)),
+            _ => return Err(ParseTopologyError::UnknownKind(kind)),
+        };
+
+        Ok((topology, width, height))
+    }
+}
+
+fn parse_dimension(dimension: &str) -> Result<(usize, bool), ParseTopologyError> {
+    let (digits, twisted) = match dimension.strip_suffix('*') {
+        Some(digits) => (digits, true),
+        None => (dimension, false),
+    };
+
+    match digits.parse() {
+        Ok(0) => Err(ParseTopologyError::Unbounded),
+        Ok(size) => Ok((size, twisted)),
+        Err(_) => Err(ParseTopologyError::InvalidDimension(dimension.to_string())),
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Topology::Plane => "plane",
+            Topology::Torus => "torus",
+            Topology::HorizontalCylinder => "horizontal cylinder",
+            Topology::VerticalCylinder => "vertical cylinder",
+            Topology::KleinBottle(TwistedEdges::TopBottom) => "Klein bottle (top/bottom twisted)",
+            Topology::KleinBottle(TwistedEdges::LeftRight) => "Klein bottle (left/right twisted)",
+            Topology::CrossSurface => "cross-surface",
+        };
+
+        f.write_str(name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseTopologyError {
+    Empty,
+    UnknownKind(char),
+    InvalidDimension(String),
+    Unbounded,
+    KleinBottleTwist,
+    UnexpectedTwist(char),
+}
+
+impl fmt::Display for ParseTopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTopologyError::Empty => write!(f, "expected a bounded grid like \"T50,50\""),
+            ParseTopologyError::UnknownKind(kind) => {
+                write!(f, "'{kind}' is not one of the grid kinds P, T, K or C")
+            }
+            ParseTopologyError::InvalidDimension(dimension) => {
+                write!(f, "\"{dimension}\" is not a valid grid dimension")
+            }
+            ParseTopologyError::Unbounded => {
+                write!(f, "unbounded (zero) dimensions are not supported")
+            }
+            ParseTopologyError::KleinBottleTwist => {
+                write!(
+                    f,
+                    "a Klein bottle needs exactly one dimension marked with '*'"
+                )
+            }
+            ParseTopologyError::UnexpectedTwist(kind) => {
+                write!(
+                    f,
+                    "only Klein bottles can mark a dimension with '*', not '{kind}'"
+                )
+            }
+        }
+    }
+}
+
+impl Error for ParseTopologyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_golly_specs() {
+        assert_eq!(
+            Topology::from_golly("P30,20"),
+            Ok((Topology::Plane, 30, 20))
+        );
+        assert_eq!(Topology::from_golly("t50"), Ok((Topology::Torus, 50, 50)));
+        assert_eq!(
+            Topology::from_golly(" C4,6 "),
+            Ok((Topology::CrossSurface, 4, 6))
+        );
+        assert_eq!(
+            Topology::from_golly("K50*,40"),
+            Ok((Topology::KleinBottle(TwistedEdges::TopBottom), 50, 40))
+        );
+        assert_eq!(
+            Topology::from_golly("K50,40*"),
+            Ok((Topology::KleinBottle(TwistedEdges::LeftRight), 50, 40))
+        );
+    }
+
+    #[test]
+    fn round_trips_golly_specs() {
+        for topology in Topology::ALL {
+            match topology.to_golly(12, 7) {
+                Some(spec) => assert_eq!(Topology::from_golly(&spec), Ok((topology, 12, 7))),
+                None => assert!(matches!(
+                    topology,
+                    Topology::HorizontalCylinder | Topology::VerticalCylinder
+                )),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_golly_specs() {
+        assert_eq!(Topology::from_golly(""), Err(ParseTopologyError::Empty));
+        assert_eq!(
+            Topology::from_golly("S10,10"),
+            Err(ParseTopologyError::UnknownKind('S'))
+        );
+        assert_eq!(
+            Topology::from_golly("T10,x"),
+            Err(ParseTopologyError::InvalidDimension("x".to_string()))
+        );
+        assert_eq!(
+            Topology::from_golly("T"),
+            Err(ParseTopologyError::InvalidDimension(String::new()))
+        );
+        assert_eq!(
+            Topology::from_golly("P0,10"),
+            Err(ParseTopologyError::Unbounded)
+        );
+        assert_eq!(
+            Topology::from_golly("K10,10"),
+            Err(ParseTopologyError::KleinBottleTwist)
+        );
+        assert_eq!(
+            Topology::from_golly("K10*,10*"),
+            Err(ParseTopologyError::KleinBottleTwist)
+        );
+        assert_eq!(
+            Topology::from_golly("T10*,10"),
+            Err(ParseTopologyError::UnexpectedTwist('T'))
+        );
+    }
+}
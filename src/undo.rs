@@ -0,0 +1,241 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::Resource;
+
+use crate::{rule::Rule, universe::Universe};
+
+/// Most edits kept for undoing.
+const MAX_EDITS: usize = 200;
+
+/// Most flipped cells kept across all edits; the oldest edits are dropped
+/// beyond this, and runs on bigger populations are not recorded at all.
+const MAX_CELLS: usize = 2_000_000;
+
+/// One undoable change: the cells it flipped, and the rule and generation
+/// count before and after it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit {
+    pub flips: Vec<(i64, i64)>,
+    pub rule: (Rule, Rule),
+    pub generation: (u64, u64),
+}
+
+impl Edit {
+    fn is_empty(&self) -> bool {
+        self.flips.is_empty()
+            && self.rule.0 == self.rule.1
+            && self.generation.0 == self.generation.1
+    }
+}
+
+/// Undo and redo stacks of edits.
+///
+/// Edits are stored as the cells they flipped, which undo themselves when
+/// flipped again, so an edit costs memory in proportion to the cells it
+/// changed rather than to the size of the board. Running the simulation is
+/// recorded the same way, as the difference between the board when the run
+/// started and when the next edit or undo finishes it.
+#[derive(Resource, Debug, Default)]
+pub struct UndoLog {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// Cells flipped so far by an edit that is still going, like a stroke.
+    pending: Vec<(i64, i64)>,
+    /// Live cells, sorted row by row, and generation from when the
+    /// simulation started running.
+    run_start: Option<(Vec<(i64, i64)>, u64)>,
+}
+
+impl UndoLog {
+    pub fn clear(&mut self) {
+        *self = UndoLog::default();
+    }
+
+    /// Sets a cell as part of the pending edit.
+    pub fn set(&mut self, universe: &mut dyn Universe, x: i64, y: i64, is_alive: bool) {
+        if universe.get(x, y) != is_alive {
+            universe.set(x, y, is_alive);
+            if universe.get(x, y) == is_alive {
+                self.pending.push((x, y));
+            }
+        }
+    }
+
+    /// Records the cells set since the last commit as one edit.
+    pub fn commit(&mut self, rule: Rule, generation: u64) {
+        let flips = std::mem::take(&mut self.pending);
+        self.record(Edit {
+            flips,
+            rule: (rule, rule),
+            generation: (generation, generation),
+        });
+    }
+
+    /// Records an edit that turned a board whose live cells were `before`
+    /// into `universe`, such as a clear or a reset.
+    pub fn record_change(
+        &mut self,
+        before: &[(i64, i64)],
+        universe: &dyn Universe,
+        rule: (Rule, Rule),
+        generation: (u64, u64),
+    ) {
+        let mut before = before.to_vec();
+        sort_rows(&mut before);
+        let flips = difference(&before, universe);
+        self.record(Edit {
+            flips,
+            rule,
+            generation,
+        });
+    }
+
+    pub fn record(&mut self, edit: Edit) {
+        if edit.is_empty() {
+            return;
+        }
+
+        self.undo.push(edit);
+        self.redo.clear();
+
+        let mut cells: usize = self.undo.iter().map(|edit| edit.flips.len()).sum();
+        while self.undo.len() > MAX_EDITS || cells > MAX_CELLS {
+            cells -= self.undo.remove(0).flips.len();
+        }
+    }
+
+    /// Notes the board before the simulation advances, unless a run is
+    /// already being recorded.
+    pub fn start_run(&mut self, universe: &dyn Universe, generation: u64) {
+        if self.run_start.is_some() {
+            return;
+        }
+
+        if universe.population() > MAX_CELLS {
+            // Too big to diff: earlier edits could no longer be undone
+            // onto the evolved board, so forget them.
+            self.clear();
+            return;
+        }
+
+        let mut cells = universe.live_cells();
+        sort_rows(&mut cells);
+        self.run_start = Some((cells, generation));
+    }
+
+    /// Records the simulation run since `start_run`, if any, as an edit.
+    pub fn finish_run(&mut self, universe: &dyn Universe, rule: Rule, generation: u64) {
+        if let Some((before, start)) = self.run_start.take() {
+            let flips = difference(&before, universe);
+            self.record(Edit {
+                flips,
+                rule: (rule, rule),
+                generation: (start, generation),
+            });
+        }
+    }
+
+    /// Reverts the latest edit on `universe`, returning it so that the
+    /// caller can restore the rule and generation it started from.
+    pub fn undo(&mut self, universe: &mut dyn Universe) -> Option<&Edit> {
+        let edit = self.undo.pop()?;
+        flip(universe, &edit.flips);
+        self.redo.push(edit);
+        self.redo.last()
+    }
+
+    /// Re-applies the latest undone edit, returning it so that the caller
+    /// can restore the rule and generation it ended with.
+    pub fn redo(&mut self, universe: &mut dyn Universe) -> Option<&Edit> {
+        let edit = self.redo.pop()?;
+        flip(universe, &edit.flips);
+        self.undo.push(edit);
+        self.undo.last()
+    }
+}
+
+/// Cells whose state differs between `before`, sorted row by row, and
+/// `universe`.
+fn difference(before: &[(i64, i64)], universe: &dyn Universe) -> Vec<(i64, i64)> {
+    let mut after = universe.live_cells();
+    sort_rows(&mut after);
+
+    // Walk both sorted lists together, keeping cells found in only one.
+    let mut flips = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() && j < after.len() {
+        match row_order(before[i]).cmp(&row_order(after[j])) {
+            Ordering::Less => {
+                flips.push(before[i]);
+                i += 1;
+            }
+            Ordering::Greater => {
+                flips.push(after[j]);
+                j += 1;
+            }
+            Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    flips.extend_from_slice(&before[i..]);
+    flips.extend_from_slice(&after[j..]);
+    flips
+}
+
+/// Sorts cells row by row, the order bounded boards list them in already,
+/// which makes the sort nearly free for them.
+fn sort_rows(cells: &mut [(i64, i64)]) {
+    cells.sort_unstable_by_key(|&cell| row_order(cell));
+}
+
+fn row_order((x, y): (i64, i64)) -> (i64, i64) {
+    (y, x)
+}
+
+fn flip(universe: &mut dyn Universe, cells: &[(i64, i64)]) {
+    for &(x, y) in cells {
+        let is_alive = universe.get(x, y);
+        universe.set(x, y, !is_alive);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid::Grid, topology::Topology, universe::sorted_cells};
+
+    #[test]
+    fn undoes_and_redoes_a_run() {
+        let mut grid = Grid::new(16, 16, Topology::Torus);
+        for (x, y) in [
+            (1, 0),
+            (2, 1),
+            (0, 2),
+            (1, 2),
+            (2, 2),
+            (9, 9),
+            (10, 9),
+            (11, 9),
+        ] {
+            grid.set(x, y, true);
+        }
+        let start = sorted_cells(&grid);
+
+        let mut log = UndoLog::default();
+        log.start_run(&grid, 0);
+        for _ in 0..7 {
+            grid.step(&Rule::LIFE);
+        }
+        log.finish_run(&grid, Rule::LIFE, 7);
+        let end = sorted_cells(&grid);
+
+        let edit = log.undo(&mut grid).unwrap();
+        assert_eq!(edit.generation, (0, 7));
+        assert_eq!(sorted_cells(&grid), start);
+
+        log.redo(&mut grid).unwrap();
+        assert_eq!(sorted_cells(&grid), end);
+    }
+}
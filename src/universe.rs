@@ -0,0 +1,183 @@
+use std::{any::Any, error::Error, fmt, str::FromStr};
+
+use bevy::utils::HashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bitgrid::BitGrid, grid::Grid, hashlife::HashLife, rule::Rule, sparse::SparseGrid,
+    topology::Topology,
+};
+
+/// Inclusive bounding box of the live cells of a universe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_x: i64,
+    pub min_y: i64,
+    pub max_x: i64,
+    pub max_y: i64,
+}
+
+impl Bounds {
+    pub fn width(&self) -> u64 {
+        self.max_x.abs_diff(self.min_x) + 1
+    }
+
+    pub fn height(&self) -> u64 {
+        self.max_y.abs_diff(self.min_y) + 1
+    }
+
+    pub fn center(&self) -> (i64, i64) {
+        (
+            self.min_x + (self.max_x - self.min_x) / 2,
+            self.min_y + (self.max_y - self.min_y) / 2,
+        )
+    }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        (self.min_x..=self.max_x).contains(&x) && (self.min_y..=self.max_y).contains(&y)
+    }
+
+    pub fn from_cells(cells: impl IntoIterator<Item = (i64, i64)>) -> Option<Bounds> {
+        cells.into_iter().fold(None, |bounds, (x, y)| {
+            Some(match bounds {
+                None => Bounds {
+                    min_x: x,
+                    min_y: y,
+                    max_x: x,
+                    max_y: y,
+                },
+                Some(b) => Bounds {
+                    min_x: b.min_x.min(x),
+                    min_y: b.min_y.min(y),
+                    max_x: b.max_x.max(x),
+                    max_y: b.max_y.max(y),
+                },
+            })
+        })
+    }
+}
+
+/// Live cells of `universe` in sorted order, for comparing boards in tests.
+#[cfg(test)]
+pub(crate) fn sorted_cells(universe: &dyn Universe) -> Vec<(i64, i64)> {
+    let mut cells = universe.live_cells();
+    cells.sort();
+    cells
+}
+
+/// Cells that came alive and cells that died in one generation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StepChanges {
+    pub births: usize,
+    pub deaths: usize,
+}
+
+/// A simulation backend: something that holds live cells and can advance
+/// them by a rule.
+///
+/// Coordinates are signed so that unbounded universes can grow in every
+/// direction; bounded ones treat anything off the board as dead and ignore
+/// writes to it.
+///
+/// `Any` lets callers reach backend-specific features, such as saving a
+/// `HashLife` quadtree as-is.
+pub trait Universe: Any + Send + Sync {
+    fn get(&self, x: i64, y: i64) -> bool;
+
+    fn set(&mut self, x: i64, y: i64, is_alive: bool);
+
+    /// Advances one generation of `rule`.
+    fn step(&mut self, rule: &Rule);
+
+    /// Advances one generation like `step`, counting births and deaths.
+    /// The default compares the live cells before and after.
+    fn step_counting(&mut self, rule: &Rule) -> StepChanges {
+        let before: HashSet<_> = self.live_cells().into_iter().collect();
+        self.step(rule);
+        let after = self.live_cells();
+
+        let births = after.iter().filter(|&cell| !before.contains(cell)).count();
+        StepChanges {
+            births,
+            deaths: before.len() + births - after.len(),
+        }
+    }
+
+    /// Advances `2^exponent` generations. Backends that can skip ahead
+    /// override this; the default simply steps one generation at a time.
+    fn step_pow2(&mut self, rule: &Rule, exponent: u32) {
+        for _ in 0..1u64 << exponent {
+            self.step(rule);
+        }
+    }
+
+    fn population(&self) -> usize;
+
+    fn live_cells(&self) -> Vec<(i64, i64)>;
+
+    fn clear(&mut self);
+
+    /// The edge gluing of a bounded universe, or `None` if it is unbounded.
+    fn topology(&self) -> Option<Topology>;
+
+    /// Changes the edge gluing. Unbounded universes ignore this.
+    fn set_topology(&mut self, _topology: Topology) {}
+
+    fn bounds(&self) -> Option<Bounds> {
+        Bounds::from_cells(self.live_cells())
+    }
+}
+
+/// Which `Universe` implementation backs the simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Backend {
+    /// `Grid`: a fixed `width` x `height` board with a topology.
+    #[default]
+    Dense,
+    /// `BitGrid`: the same bounded board packed 64 cells to a word.
+    BitPacked,
+    /// `SparseGrid`: an unbounded plane that stores only live cells.
+    Sparse,
+    /// `HashLife`: an unbounded memoised quadtree for huge generation jumps.
+    HashLife,
+}
+
+impl Backend {
+    pub fn create(&self, width: usize, height: usize, topology: Topology) -> Box<dyn Universe> {
+        match self {
+            Backend::Dense => Box::new(Grid::new(width, height, topology)),
+            Backend::BitPacked => Box::new(BitGrid::new(width, height, topology)),
+            Backend::Sparse => Box::new(SparseGrid::new()),
+            Backend::HashLife => Box::new(HashLife::new()),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = ParseBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dense" => Ok(Backend::Dense),
+            "bitpacked" => Ok(Backend::BitPacked),
+            "sparse" => Ok(Backend::Sparse),
+            "hashlife" => Ok(Backend::HashLife),
+            _ => Err(ParseBackendError(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseBackendError(String);
+
+impl fmt::Display for ParseBackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" is not a backend; expected dense, bitpacked, sparse or hashlife",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseBackendError {}